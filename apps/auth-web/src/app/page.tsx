@@ -8,6 +8,7 @@ import { toast } from "sonner"
 import { Button } from "@/components/ui/button"
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card"
 import { ModeToggle } from "@/components/mode-toggle"
+import { rememberDesktopState } from "@/lib/auth/desktop-state"
 
 export default function HomePage() {
   const router = useRouter()
@@ -15,6 +16,11 @@ export default function HomePage() {
 
   const isLoggedIn = !!jwt && !!user
 
+  // Kept for the deep link back to the desktop app once sign-in completes
+  useEffect(() => {
+    rememberDesktopState(new URLSearchParams(window.location.search).get('state'))
+  }, [])
+
   // Redirect to success page if already authenticated
   useEffect(() => {
     if (isLoggedIn) {
@@ -11,6 +11,7 @@ import { Button } from "@/components/ui/button"
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card"
 import { ModeToggle } from "@/components/mode-toggle"
 import { generatePKCE } from "@/lib/auth/pkce"
+import { DESKTOP_STATE_KEY } from "@/lib/auth/desktop-state"
 
 function SuccessPageContent() {
   const router = useRouter()
@@ -170,8 +171,11 @@ function SuccessPageContent() {
         }
       }
       
-      // Fallback to deep link with PKCE verifier
-      const deepLinkUrl = `${process.env.NEXT_PUBLIC_AUTH_REDIRECT_URL}?code=${encodeURIComponent(authCode)}&verifier=${encodeURIComponent(verifier)}`
+      // Fallback to deep link with PKCE verifier, plus the desktop app's sign-in state so
+      // the app can tell this callback answers a sign-in it started
+      const desktopState = sessionStorage.getItem(DESKTOP_STATE_KEY)
+      const stateParam = desktopState ? `&state=${encodeURIComponent(desktopState)}` : ''
+      const deepLinkUrl = `${process.env.NEXT_PUBLIC_AUTH_REDIRECT_URL}?code=${encodeURIComponent(authCode)}&verifier=${encodeURIComponent(verifier)}${stateParam}`
       window.location.href = deepLinkUrl
       
       // Also show success message
@@ -0,0 +1,17 @@
+/**
+ * Sign-in state handed over by the desktop app
+ */
+
+export const DESKTOP_STATE_KEY = 'symlog_desktop_state'
+
+// Matches what the desktop shell generates and accepts back on its callback route
+const STATE_PATTERN = /^[A-Za-z0-9._~-]{1,128}$/
+
+/**
+ * Remember the desktop app's state so the callback deep link can carry it back
+ */
+export function rememberDesktopState(state: string | null): void {
+  if (state && STATE_PATTERN.test(state)) {
+    sessionStorage.setItem(DESKTOP_STATE_KEY, state)
+  }
+}
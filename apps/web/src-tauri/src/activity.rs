@@ -0,0 +1,58 @@
+use std::collections::BTreeSet;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use tokio::sync::watch;
+use crate::error::CommandError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseReason {
+    ScreenLocked,
+    Sleeping,
+    Idle,
+}
+
+// Background network jobs run only while nothing holds them paused
+pub struct BackgroundActivity {
+    reasons: watch::Sender<BTreeSet<PauseReason>>,
+}
+
+impl Default for BackgroundActivity {
+    fn default() -> Self {
+        Self {
+            reasons: watch::Sender::new(BTreeSet::new()),
+        }
+    }
+}
+
+impl BackgroundActivity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pause(&self, reason: PauseReason) {
+        self.reasons.send_if_modified(|reasons| reasons.insert(reason));
+    }
+
+    pub fn resume(&self, reason: PauseReason) {
+        self.reasons.send_if_modified(|reasons| reasons.remove(&reason));
+    }
+
+    pub fn paused_by(&self) -> Vec<PauseReason> {
+        self.reasons.borrow().iter().copied().collect()
+    }
+
+    pub async fn wait_until_active(&self) {
+        let mut receiver = self.reasons.subscribe();
+        // The sender lives as long as this state, so the wait cannot fail
+        let _ = receiver.wait_for(BTreeSet::is_empty).await;
+    }
+}
+
+// Tauri commands
+#[command]
+pub async fn get_background_activity(
+    activity: State<'_, BackgroundActivity>,
+) -> Result<Vec<PauseReason>, CommandError> {
+    Ok(activity.paused_by())
+}
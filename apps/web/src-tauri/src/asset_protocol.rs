@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, UriSchemeContext, UriSchemeResponder};
+use percent_encoding::percent_decode_str;
+use crate::files::{sniff_mime_type, FileError};
+use crate::share::IMPORTED_CONVERSATIONS_DIR;
+
+pub const ASSET_SCHEME: &str = "symlog-asset";
+// Every partial response is capped, whatever range the client asked for, so seeking in a
+// long video never loads it whole; media elements ask again for the rest
+const MAX_RANGE_LEN: u64 = 4 * 1024 * 1024;
+
+// Named directories the webview may read from; everything else is refused
+pub struct AssetRoots {
+    roots: HashMap<String, PathBuf>,
+}
+
+impl AssetRoots {
+    pub fn new(app: &AppHandle) -> Result<Self, FileError> {
+        let path = app.path();
+        let data_dir = path
+            .app_data_dir()
+            .map_err(|e| FileError::InvalidPath(e.to_string()))?;
+
+        // Only directories the shell itself writes to; attachments are served by the blob store
+        let conversations = data_dir.join(IMPORTED_CONVERSATIONS_DIR);
+        fs::create_dir_all(&conversations)?;
+        // Canonical roots make the prefix check below immune to symlinked app dirs
+        let roots = HashMap::from([(IMPORTED_CONVERSATIONS_DIR.to_string(), conversations.canonicalize()?)]);
+
+        Ok(Self { roots })
+    }
+
+    pub fn resolve(&self, uri_path: &str) -> Result<PathBuf, FileError> {
+        let decoded = percent_decode_str(uri_path.trim_start_matches('/'))
+            .decode_utf8()
+            .map_err(|e| FileError::InvalidPath(e.to_string()))?;
+        let (root_name, relative) = decoded
+            .split_once('/')
+            .ok_or_else(|| FileError::InvalidPath(decoded.to_string()))?;
+        let root = self
+            .roots
+            .get(root_name)
+            .ok_or_else(|| FileError::AccessDenied(format!("unknown root {}", root_name)))?;
+
+        let relative = Path::new(relative);
+        if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(FileError::AccessDenied(format!("path {}", decoded)));
+        }
+
+        // Canonicalizing resolves symlinks, so a link pointing out of the root is caught too
+        let resolved = root.join(relative).canonicalize()?;
+        if !resolved.starts_with(root) || !resolved.is_file() {
+            return Err(FileError::AccessDenied(format!("path {}", decoded)));
+        }
+        Ok(resolved)
+    }
+}
+
+enum ByteRange {
+    Full,
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+// Only single ranges are supported; media elements never ask for more
+fn parse_range(value: Option<&str>, len: u64) -> ByteRange {
+    let Some(spec) = value.and_then(|v| v.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Unsatisfiable;
+    };
+
+    let (start, end) = match (start.trim().parse::<u64>(), end.trim().parse::<u64>()) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Ok(start), Err(_)) if end.trim().is_empty() => (start, u64::MAX),
+        (Err(_), Ok(suffix)) if start.trim().is_empty() => (len.saturating_sub(suffix), u64::MAX),
+        _ => return ByteRange::Unsatisfiable,
+    };
+
+    if len == 0 || start > end || start >= len {
+        ByteRange::Unsatisfiable
+    } else {
+        capped(start, end, len)
+    }
+}
+
+fn capped(start: u64, end: u64, len: u64) -> ByteRange {
+    let end = end
+        .min(start.saturating_add(MAX_RANGE_LEN - 1))
+        .min(len - 1);
+    ByteRange::Partial(start, end)
+}
+
+fn etag_for(path: &Path) -> Result<String, FileError> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    Ok(format!("W/\"{:x}-{:x}\"", metadata.len(), modified))
+}
+
+// Serves `path` honouring Range and If-None-Match; shared by every local-content scheme
+pub fn file_response(
+    request: &Request<Vec<u8>>,
+    path: &Path,
+    content_type: &str,
+    etag: &str,
+    cache_control: &str,
+) -> Result<Response<Vec<u8>>, FileError> {
+    let build_error = |e: tauri::http::Error| FileError::IoError(e.to_string());
+    let base = || {
+        Response::builder()
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .header(header::ACCEPT_RANGES, "bytes")
+            .header("X-Content-Type-Options", "nosniff")
+    };
+
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok());
+    if if_none_match.is_some_and(|tags| tags.split(',').any(|t| t.trim() == etag || t.trim() == "*")) {
+        return base()
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Vec::new())
+            .map_err(build_error);
+    }
+
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let range = request.headers().get(header::RANGE).and_then(|v| v.to_str().ok());
+    match parse_range(range, len) {
+        ByteRange::Full => {
+            let mut body = Vec::with_capacity(len as usize);
+            file.read_to_end(&mut body)?;
+            base()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::CONTENT_LENGTH, body.len())
+                .body(body)
+                .map_err(build_error)
+        }
+        ByteRange::Partial(start, end) => {
+            file.seek(SeekFrom::Start(start))?;
+            let mut body = Vec::with_capacity((end - start + 1) as usize);
+            file.take(end - start + 1).read_to_end(&mut body)?;
+            base()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::CONTENT_LENGTH, body.len())
+                .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len))
+                .body(body)
+                .map_err(build_error)
+        }
+        ByteRange::Unsatisfiable => base()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Vec::new())
+            .map_err(build_error),
+    }
+}
+
+pub fn status_response(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
+
+pub fn handle_asset_protocol(ctx: UriSchemeContext<'_, tauri::Wry>, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let app = ctx.app_handle().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let uri_path = request.uri().path().to_string();
+        let Some(roots) = app.try_state::<AssetRoots>() else {
+            responder.respond(status_response(StatusCode::SERVICE_UNAVAILABLE));
+            return;
+        };
+
+        let response = roots.resolve(&uri_path).and_then(|path| {
+            let content_type = sniff_mime_type(&path)?;
+            let etag = etag_for(&path)?;
+            file_response(&request, &path, &content_type, &etag, "no-cache")
+        });
+
+        match response {
+            Ok(response) => responder.respond(response),
+            Err(FileError::AccessDenied(reason)) => {
+                log::warn!("Refused asset request {}: {}", uri_path, reason);
+                responder.respond(status_response(StatusCode::FORBIDDEN));
+            }
+            Err(e) => {
+                log::warn!("Failed to serve asset {}: {}", uri_path, e);
+                responder.respond(status_response(StatusCode::NOT_FOUND));
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEN: u64 = 3 * MAX_RANGE_LEN;
+
+    fn partial(range: &str) -> (u64, u64) {
+        match parse_range(Some(range), LEN) {
+            ByteRange::Partial(start, end) => (start, end),
+            _ => panic!("{} was not served as a partial response", range),
+        }
+    }
+
+    #[test]
+    fn explicit_ranges_are_capped() {
+        assert_eq!(partial("bytes=0-99"), (0, 99));
+        assert_eq!(partial(&format!("bytes=0-{}", LEN - 1)), (0, MAX_RANGE_LEN - 1));
+        assert_eq!(partial("bytes=10-"), (10, 10 + MAX_RANGE_LEN - 1));
+        assert_eq!(partial(&format!("bytes=-{}", LEN)), (0, MAX_RANGE_LEN - 1));
+        assert_eq!(partial("bytes=-100"), (LEN - 100, LEN - 1));
+        assert_eq!(partial(&format!("bytes={}-{}", LEN - 10, LEN + 10)), (LEN - 10, LEN - 1));
+    }
+
+    #[test]
+    fn unsatisfiable_ranges_are_refused() {
+        assert!(matches!(parse_range(Some(&format!("bytes={}-", LEN)), LEN), ByteRange::Unsatisfiable));
+        assert!(matches!(parse_range(Some("bytes=20-10"), LEN), ByteRange::Unsatisfiable));
+        assert!(matches!(parse_range(Some("bytes=0-"), 0), ByteRange::Unsatisfiable));
+        assert!(matches!(parse_range(None, LEN), ByteRange::Full));
+    }
+
+    #[test]
+    fn only_range_requests_get_a_capped_partial_response() {
+        let dir = std::env::temp_dir().join(format!("symlog-assets-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("clip.mp4");
+        fs::write(&path, vec![0u8; (MAX_RANGE_LEN + 10) as usize]).unwrap();
+        let len = MAX_RANGE_LEN + 10;
+
+        let request = Request::builder().body(Vec::new()).unwrap();
+        let response = file_response(&request, &path, "video/mp4", "W/\"1\"", "no-cache").unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body().len() as u64, len);
+
+        let request = Request::builder()
+            .header(header::RANGE, "bytes=0-")
+            .body(Vec::new())
+            .unwrap();
+        let response = file_response(&request, &path, "video/mp4", "W/\"1\"", "no-cache").unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.body().len() as u64, MAX_RANGE_LEN);
+        assert_eq!(
+            response.headers()[header::CONTENT_RANGE],
+            format!("bytes 0-{}/{}", MAX_RANGE_LEN - 1, len)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, DragDropEvent, Emitter, Manager, State, WindowEvent};
+use base64::{engine::general_purpose, Engine as _};
+use sha2::{Digest, Sha256};
+use chrono::Utc;
+use uuid::Uuid;
+use crate::blob_store::BlobStore;
+use crate::error::CommandError;
+use crate::files::{sniff_bytes, FileError};
+
+const THUMBNAIL_SIZE: u32 = 256;
+// Extracted text is context for the model, not an archive of the document
+const MAX_EXTRACTED_CHARS: usize = 100_000;
+
+const DOCX_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+// The lists in types/attachments.ts, in the same order
+const SUPPORTED_IMAGE_TYPES: &[&str] = &[
+    "image/jpeg",
+    "image/jpg",
+    "image/png",
+    "image/gif",
+    "image/webp",
+    "image/svg+xml",
+];
+const SUPPORTED_DOCUMENT_TYPES: &[&str] = &[
+    "application/pdf",
+    DOCX_TYPE,
+    "text/plain",
+    "text/markdown",
+    "text/csv",
+    "application/json",
+    "text/html",
+    "text/css",
+    "text/javascript",
+    "text/typescript",
+];
+const SUPPORTED_CODE_TYPES: &[&str] = &[
+    "text/javascript",
+    "text/typescript",
+    "text/python",
+    "text/java",
+    "text/cpp",
+    "text/c",
+    "text/csharp",
+    "text/go",
+    "text/rust",
+    "text/php",
+    "text/ruby",
+    "text/swift",
+    "text/kotlin",
+];
+// The audio and video types isFileTypeSupported adds to the lists above
+const SUPPORTED_MEDIA_TYPES: &[&str] = &["audio/mpeg", "audio/mp3", "audio/wav", "video/mp4", "video/webm"];
+
+// Descriptors kept for re-dropped files; older ones are simply ingested again
+const DESCRIPTOR_CACHE_CAPACITY: usize = 64;
+
+// Mirrors MAX_FILE_SIZE, MAX_FILES_PER_MESSAGE and isFileTypeSupported in types/attachments.ts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentPolicy {
+    pub max_file_size: u64,
+    pub max_files: usize,
+    pub allowed_types: Vec<String>,
+}
+
+impl Default for AttachmentPolicy {
+    fn default() -> Self {
+        let mut allowed_types: Vec<String> = Vec::new();
+        for mime_type in [SUPPORTED_IMAGE_TYPES, SUPPORTED_DOCUMENT_TYPES, SUPPORTED_CODE_TYPES, SUPPORTED_MEDIA_TYPES]
+            .concat()
+        {
+            if !allowed_types.iter().any(|allowed| allowed.as_str() == mime_type) {
+                allowed_types.push(mime_type.to_string());
+            }
+        }
+
+        Self {
+            max_file_size: 10 * 1024 * 1024,
+            max_files: 5,
+            allowed_types,
+        }
+    }
+}
+
+// Serializes to the shape of FileAttachment / ImageAttachment / DocumentAttachment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentDescriptor {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub mime_type: String,
+    pub size: u64,
+    pub sha256: String,
+    pub attachment_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+    pub uploaded_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentRejection {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttachmentDropResult {
+    pub attachments: Vec<AttachmentDescriptor>,
+    pub rejected: Vec<AttachmentRejection>,
+}
+
+// Most recently ingested descriptors, looked up by SHA-256
+#[derive(Default)]
+struct DescriptorCache {
+    entries: VecDeque<AttachmentDescriptor>,
+}
+
+impl DescriptorCache {
+    fn get(&mut self, sha256: &str) -> Option<AttachmentDescriptor> {
+        let position = self.entries.iter().position(|entry| entry.sha256 == sha256)?;
+        let entry = self.entries.remove(position)?;
+        self.entries.push_front(entry.clone());
+        Some(entry)
+    }
+
+    fn insert(&mut self, descriptor: AttachmentDescriptor) {
+        self.entries.retain(|entry| entry.sha256 != descriptor.sha256);
+        self.entries.push_front(descriptor);
+        self.entries.truncate(DESCRIPTOR_CACHE_CAPACITY);
+    }
+
+    fn remove(&mut self, sha256: &str) {
+        self.entries.retain(|entry| entry.sha256 != sha256);
+    }
+}
+
+#[derive(Default)]
+pub struct AttachmentIngestor {
+    policy: AttachmentPolicy,
+    // So dropping the same file twice yields the same attachment
+    by_hash: Mutex<DescriptorCache>,
+}
+
+impl AttachmentIngestor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ingest_paths(&self, paths: &[PathBuf], blob_store: &BlobStore) -> AttachmentDropResult {
+        let mut result = AttachmentDropResult::default();
+
+        for (index, path) in paths.iter().enumerate() {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string());
+
+            if index >= self.policy.max_files {
+                result.rejected.push(AttachmentRejection {
+                    name,
+                    reason: format!("At most {} files can be attached at once", self.policy.max_files),
+                });
+                continue;
+            }
+
+            match self.ingest(path, blob_store) {
+                Ok(descriptor) => result.attachments.push(descriptor),
+                Err(e) => {
+                    log::warn!("Rejected dropped file {}: {}", path.display(), e);
+                    result.rejected.push(AttachmentRejection {
+                        name,
+                        reason: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        result
+    }
+
+    fn ingest(&self, path: &Path, blob_store: &BlobStore) -> Result<AttachmentDescriptor, FileError> {
+        let metadata = fs::metadata(path)?;
+        if !metadata.is_file() {
+            return Err(FileError::InvalidPath("Only files can be attached".to_string()));
+        }
+        if metadata.len() > self.policy.max_file_size {
+            return Err(FileError::PolicyViolation(format!(
+                "File exceeds the {} MB limit",
+                self.policy.max_file_size / (1024 * 1024)
+            )));
+        }
+
+        let bytes = fs::read(path)?;
+        let mime_type = sniff_bytes(&bytes, path).to_string();
+        if !self.policy.allowed_types.contains(&mime_type) {
+            return Err(FileError::PolicyViolation(format!("Unsupported file type: {}", mime_type)));
+        }
+
+        let sha256 = hex_digest(&bytes);
+        {
+            // Only while its blob is still stored; GC may have collected it since
+            let mut by_hash = self.by_hash.lock().unwrap();
+            match by_hash.get(&sha256) {
+                Some(existing) if existing.url.is_some() && blob_store.contains(&sha256) => return Ok(existing),
+                Some(_) => by_hash.remove(&sha256),
+                None => {}
+            }
+        }
+
+        let mut descriptor = AttachmentDescriptor {
+            id: Uuid::new_v4().to_string(),
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            attachment_type: attachment_type(&mime_type).to_string(),
+            mime_type,
+            size: bytes.len() as u64,
+            sha256: sha256.clone(),
+            url: None,
+            preview: None,
+            uploaded_at: Utc::now().timestamp_millis(),
+            width: None,
+            height: None,
+            page_count: None,
+            content: None,
+        };
+
+        match descriptor.mime_type.as_str() {
+            "image/svg+xml" => {
+                descriptor.preview = Some(data_url("image/svg+xml", &bytes));
+            }
+            mime if mime.starts_with("image/") => {
+                // An undecodable image is still attachable, just without a thumbnail
+                match generate_thumbnail(&bytes) {
+                    Ok((width, height, preview)) => {
+                        descriptor.width = Some(width);
+                        descriptor.height = Some(height);
+                        descriptor.preview = Some(preview);
+                    }
+                    Err(e) => log::warn!("Failed to generate thumbnail for {}: {}", path.display(), e),
+                }
+            }
+            "application/pdf" => {
+                // Like an undecodable image, an unreadable PDF is attached without its text
+                match extract_pdf_pages(&bytes) {
+                    Ok(pages) => {
+                        descriptor.page_count = Some(pages.len());
+                        descriptor.content = Some(truncate_text(pages.join("\n\n")));
+                    }
+                    Err(e) => log::warn!("Failed to extract text from {}: {}", path.display(), e),
+                }
+            }
+            DOCX_TYPE => {
+                // Like a PDF, an unreadable document is attached without its text
+                match extract_docx_text(&bytes) {
+                    Ok(text) => descriptor.content = Some(truncate_text(text)),
+                    Err(e) => log::warn!("Failed to extract text from {}: {}", path.display(), e),
+                }
+            }
+            mime if mime.starts_with("text/") || mime == "application/json" => {
+                descriptor.content = Some(truncate_text(String::from_utf8_lossy(&bytes).to_string()));
+            }
+            _ => {}
+        }
+
+        // The blob is unowned until the chat references it; GC grace covers the gap
+        match blob_store.put(&bytes, &descriptor.mime_type, None) {
+            Ok(entry) => descriptor.url = Some(entry.url()),
+            Err(e) => log::warn!("Failed to store attachment {} as blob: {}", path.display(), e),
+        }
+
+        self.by_hash.lock().unwrap().insert(descriptor.clone());
+        Ok(descriptor)
+    }
+}
+
+pub fn hex_digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Same checks in the same order as getAttachmentType, so a type on both the document and
+// code lists (text/javascript, text/typescript) is a document on both sides
+fn attachment_type(mime_type: &str) -> &'static str {
+    if SUPPORTED_IMAGE_TYPES.contains(&mime_type) {
+        "image"
+    } else if SUPPORTED_DOCUMENT_TYPES.contains(&mime_type) {
+        "document"
+    } else if SUPPORTED_CODE_TYPES.contains(&mime_type) {
+        "code"
+    } else if mime_type.starts_with("audio/") {
+        "audio"
+    } else if mime_type.starts_with("video/") {
+        "video"
+    } else if mime_type.contains("json") || mime_type.contains("csv") {
+        "data"
+    } else {
+        "other"
+    }
+}
+
+fn data_url(mime_type: &str, bytes: &[u8]) -> String {
+    format!("data:{};base64,{}", mime_type, general_purpose::STANDARD.encode(bytes))
+}
+
+fn generate_thumbnail(bytes: &[u8]) -> Result<(u32, u32, String), image::ImageError> {
+    let image = image::load_from_memory(bytes)?;
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+
+    let mut png = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut png, image::ImageFormat::Png)?;
+    Ok((image.width(), image.height(), data_url("image/png", png.get_ref())))
+}
+
+// pdf_extract panics on some malformed files; caught here so one bad PDF cannot take the
+// rest of a drop batch down with it
+fn extract_pdf_pages(bytes: &[u8]) -> Result<Vec<String>, String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| pdf_extract::extract_text_from_mem_by_pages(bytes))) {
+        Ok(Ok(pages)) => Ok(pages),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("the PDF parser panicked".to_string()),
+    }
+}
+
+fn extract_docx_text(bytes: &[u8]) -> Result<String, FileError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| FileError::EncodingError(e.to_string()))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|e| FileError::EncodingError(e.to_string()))?
+        .read_to_string(&mut xml)?;
+
+    // Paragraph and break elements become newlines; every other tag is dropped
+    let mut text = String::with_capacity(xml.len() / 4);
+    let mut rest = xml.as_str();
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        if tag == "/w:p" || tag.starts_with("w:br") {
+            text.push('\n');
+        } else if tag.starts_with("w:tab") && !tag.starts_with("w:tabs") {
+            text.push('\t');
+        }
+        rest = &rest[start + end + 1..];
+    }
+
+    Ok(text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&"))
+}
+
+fn truncate_text(text: String) -> String {
+    match text.char_indices().nth(MAX_EXTRACTED_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text,
+    }
+}
+
+pub fn handle_window_event(app: &AppHandle, event: &WindowEvent) {
+    let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event else {
+        return;
+    };
+
+    let app = app.clone();
+    let paths = paths.clone();
+    // Hashing, thumbnailing and text extraction must not stall the event loop
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = app
+            .state::<AttachmentIngestor>()
+            .ingest_paths(&paths, &app.state::<BlobStore>());
+        if let Err(e) = app.emit("attachments_dropped", &result) {
+            log::error!("Failed to emit dropped attachments: {}", e);
+        }
+    });
+}
+
+// Tauri commands
+#[command]
+pub async fn get_attachment_policy(
+    ingestor: State<'_, AttachmentIngestor>,
+) -> Result<AttachmentPolicy, CommandError> {
+    Ok(ingestor.policy.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TS_TYPES: &str = include_str!("../../src/types/attachments.ts");
+
+    // The quoted strings between `start` and the next `end` in types/attachments.ts
+    fn ts_strings(start: &str, end: &str) -> Vec<&'static str> {
+        let from = TS_TYPES.find(start).unwrap_or_else(|| panic!("{} not found", start)) + start.len();
+        let body = &TS_TYPES[from..from + TS_TYPES[from..].find(end).unwrap()];
+        body.split('\'').skip(1).step_by(2).collect()
+    }
+
+    #[test]
+    fn lists_match_types_attachments_ts() {
+        assert_eq!(ts_strings("SUPPORTED_IMAGE_TYPES = [", "]"), SUPPORTED_IMAGE_TYPES);
+        assert_eq!(ts_strings("SUPPORTED_DOCUMENT_TYPES = [", "]"), SUPPORTED_DOCUMENT_TYPES);
+        assert_eq!(ts_strings("SUPPORTED_CODE_TYPES = [", "]"), SUPPORTED_CODE_TYPES);
+        assert_eq!(ts_strings("...SUPPORTED_CODE_TYPES,", "]"), SUPPORTED_MEDIA_TYPES);
+    }
+
+    #[test]
+    fn attachment_types_follow_get_attachment_type() {
+        let cases = [
+            ("image/png", "image"),
+            ("application/pdf", "document"),
+            (DOCX_TYPE, "document"),
+            ("text/csv", "document"),
+            ("application/json", "document"),
+            ("text/javascript", "document"),
+            ("text/typescript", "document"),
+            ("text/rust", "code"),
+            ("audio/wav", "audio"),
+            ("video/webm", "video"),
+            ("application/ld+json", "data"),
+            ("application/zip", "other"),
+        ];
+        for (mime_type, expected) in cases {
+            assert_eq!(attachment_type(mime_type), expected, "{}", mime_type);
+        }
+    }
+}
@@ -1,15 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use tauri::{command, AppHandle, Manager, State};
 use tauri_plugin_store::{Store, StoreExt};
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
-use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use argon2::password_hash::{rand_core::{OsRng, RngCore}, SaltString};
 use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
 use sha2::{Digest, Sha256};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use uuid::Uuid;
+use zeroize::Zeroizing;
 use url::Url;
 use thiserror::Error;
+use crate::backend::BackendClient;
+use crate::device::DeviceManager;
+use crate::dpop::DeviceKey;
+use crate::error::CommandError;
+use crate::jwt::JwtValidator;
+use crate::keychain::KeyStorage;
+use crate::migrations::{self, SCHEMA_VERSION_KEY};
+use crate::vault::Vault;
 
 #[derive(Error, Debug)]
 pub enum AuthError {
@@ -23,10 +37,95 @@ pub enum AuthError {
     StorageError(String),
     #[error("Crypto error: {0}")]
     CryptoError(String),
+    #[error("Key storage unavailable: {0}")]
+    KeyUnavailable(String),
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
     #[error("Deep link registration failed: {0}")]
     DeepLinkError(String),
+    #[error("Invalid access token: {0}")]
+    InvalidToken(String),
+    #[error("Network error: {0}")]
+    NetworkError(String),
+    #[error("Confirmation required: {0}")]
+    ConfirmationRequired(String),
+    #[error("Unknown session: {0}")]
+    UnknownSession(String),
+    #[error("The current device's session cannot be revoked remotely; sign out instead")]
+    CurrentSession,
+}
+
+pub const AUTH_STORE: &str = "auth.json";
+
+// Calibration target for a single key derivation on a fresh install
+const KDF_TARGET_LATENCY: Duration = Duration::from_millis(250);
+const KDF_MAX_T_COST: u32 = 10;
+// Derived keys kept in memory; each entry saves one full Argon2 run
+const KEY_CACHE_CAPACITY: usize = 32;
+
+// Keyring account holding the store keys; they never live in auth.json itself
+const STORE_KEY_ACCOUNT: &str = "auth-store-keys";
+const STORE_KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+// Marks a passphrase-layer value sealed with the AEAD; unmarked values are legacy XOR
+const SEALED_PREFIX: &str = "x1:";
+
+// Store keys older than this are rotated by the background task
+pub const STORE_KEY_MAX_AGE: chrono::Duration = chrono::Duration::days(30);
+
+// Phrase the caller must echo back before a factory reset is carried out
+pub const FACTORY_RESET_CONFIRMATION: &str = "RESET SYMLOG";
+
+// Every key in auth.json belongs to exactly one namespace, identified by its prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreNamespace {
+    // Encrypted auth sessions, `session_<id>`
+    Session,
+    // Encrypted device-bound secrets, `secret_<name>`
+    Secret,
+    // Device identity records, `device_<name>`
+    Device,
+    // Material every encrypted value depends on, `key_<name>`
+    KeyMaterial,
+}
+
+impl StoreNamespace {
+    pub const ALL: &'static [StoreNamespace] = &[
+        StoreNamespace::Session,
+        StoreNamespace::Secret,
+        StoreNamespace::Device,
+        StoreNamespace::KeyMaterial,
+    ];
+
+    fn prefix(self) -> &'static str {
+        match self {
+            StoreNamespace::Session => "session_",
+            StoreNamespace::Secret => "secret_",
+            StoreNamespace::Device => "device_",
+            StoreNamespace::KeyMaterial => "key_",
+        }
+    }
+
+    pub fn key(self, name: &str) -> String {
+        format!("{}{}", self.prefix(), name)
+    }
+
+    pub fn of(key: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|ns| key.starts_with(ns.prefix()))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignOutSummary {
+    pub sessions_revoked: u64,
+    pub local_sessions_cleared: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RevokeAllResult {
+    sessions_revoked: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +137,52 @@ pub struct AuthToken {
     pub scope: Option<String>,
 }
 
+// Tokens as `auth:validateAuthCode` and `refreshTokens:rotateRefreshToken` return them
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssuedTokens {
+    access_token: String,
+    refresh_token: String,
+    access_token_expires_at: f64,
+    #[serde(default)]
+    token_type: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+impl IssuedTokens {
+    fn into_token(self) -> Result<AuthToken, AuthError> {
+        let expires_at = Utc
+            .timestamp_millis_opt(self.access_token_expires_at as i64)
+            .single()
+            .ok_or_else(|| AuthError::InvalidToken("invalid accessTokenExpiresAt".to_string()))?;
+        Ok(AuthToken {
+            access_token: self.access_token,
+            refresh_token: self.refresh_token,
+            expires_at,
+            token_type: self.token_type.unwrap_or_else(|| "Bearer".to_string()),
+            scope: self.scope,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CodeExchange {
+    user_id: String,
+    user_email: Option<String>,
+    wallet_address: Option<String>,
+    session: IssuedTokens,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenGrant {
+    pub user_id: String,
+    pub email: Option<String>,
+    pub wallet_address: Option<String>,
+    pub token: AuthToken,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PKCEChallenge {
     pub verifier: String,
@@ -60,6 +205,227 @@ pub struct AuthSession {
     pub device_info: DeviceInfo,
 }
 
+// Stored form of every encrypted value. Expiry metadata stays in plaintext so expired
+// entries can be swept without the per-session passphrase; `key_version` names the store
+// key the payload is wrapped with, 0 meaning it predates store keys and is unwrapped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    key_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pkce_expires_at: Option<DateTime<Utc>>,
+    payload: String,
+}
+
+impl Envelope {
+    // Entries from before the envelope format are a bare encrypted string
+    fn from_stored(value: serde_json::Value) -> Result<Self, AuthError> {
+        match value {
+            serde_json::Value::String(payload) => Ok(Self {
+                key_version: 0,
+                expires_at: None,
+                pkce_expires_at: None,
+                payload,
+            }),
+            value => serde_json::from_value(value).map_err(|e| AuthError::CryptoError(format!("Stored value is malformed: {}", e))),
+        }
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now) || self.challenge_expired(now)
+    }
+
+    fn challenge_expired(&self, now: DateTime<Utc>) -> bool {
+        self.pkce_expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+// Install-wide key wrapping every envelope payload. Rotating it only needs the shell, not
+// the per-session passphrases, because the passphrase-bound layer sits underneath.
+#[derive(Clone)]
+struct StoreKey {
+    version: u32,
+    key: Zeroizing<Vec<u8>>,
+    created_at: DateTime<Utc>,
+    // Imported from an auth.json that kept its key in plaintext and XOR-wrapped entries;
+    // only ever used to unwrap them once while they are moved to an AEAD key
+    legacy: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreKeyRecord {
+    version: u32,
+    key: String,
+    created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    legacy: bool,
+}
+
+impl StoreKey {
+    fn generate(version: u32) -> Self {
+        let mut key = Zeroizing::new(vec![0u8; STORE_KEY_LEN]);
+        OsRng.fill_bytes(&mut key);
+        Self {
+            version,
+            key,
+            created_at: Utc::now(),
+            legacy: false,
+        }
+    }
+
+    fn to_record(&self) -> StoreKeyRecord {
+        StoreKeyRecord {
+            version: self.version,
+            key: general_purpose::STANDARD.encode(self.key.as_slice()),
+            created_at: self.created_at,
+            legacy: self.legacy,
+        }
+    }
+
+    fn from_record(record: StoreKeyRecord) -> Result<Self, AuthError> {
+        let key = Zeroizing::new(
+            general_purpose::STANDARD
+                .decode(&record.key)
+                .map_err(|e| AuthError::CryptoError(format!("Stored value is malformed: {}", e)))?,
+        );
+        if key.len() != STORE_KEY_LEN {
+            return Err(AuthError::CryptoError(format!(
+                "Store key v{} is {} bytes, expected {}",
+                record.version,
+                key.len(),
+                STORE_KEY_LEN
+            )));
+        }
+        Ok(Self {
+            version: record.version,
+            key,
+            created_at: record.created_at,
+            legacy: record.legacy,
+        })
+    }
+
+    // The `key_store_key` record older versions kept inside auth.json
+    fn from_legacy_value(value: serde_json::Value) -> Result<Self, AuthError> {
+        let record: StoreKeyRecord =
+            serde_json::from_value(value).map_err(|e| AuthError::CryptoError(format!("Stored value is malformed: {}", e)))?;
+        Ok(Self {
+            legacy: true,
+            ..Self::from_record(record)?
+        })
+    }
+}
+
+// What the keyring holds: the active key and, between staging a rotation and the store
+// being saved, the key every entry on disk is still wrapped with
+struct StoreKeys {
+    active: StoreKey,
+    previous: Option<StoreKey>,
+}
+
+impl StoreKeys {
+    fn find(&self, version: u32) -> Option<&StoreKey> {
+        std::iter::once(&self.active)
+            .chain(self.previous.as_ref())
+            .find(|key| key.version == version)
+    }
+
+    fn to_secret(&self) -> Result<Zeroizing<String>, AuthError> {
+        let records: Vec<StoreKeyRecord> = self
+            .previous
+            .iter()
+            .chain(std::iter::once(&self.active))
+            .map(StoreKey::to_record)
+            .collect();
+        serde_json::to_string(&records)
+            .map(Zeroizing::new)
+            .map_err(|e| AuthError::StorageError(e.to_string()))
+    }
+
+    fn from_secret(secret: &str) -> Result<Self, AuthError> {
+        let records: Vec<StoreKeyRecord> =
+            serde_json::from_str(secret).map_err(|e| AuthError::StorageError(e.to_string()))?;
+        let mut keys = records
+            .into_iter()
+            .map(StoreKey::from_record)
+            .collect::<Result<Vec<_>, _>>()?;
+        keys.sort_by_key(|key| key.version);
+        let active = keys
+            .pop()
+            .ok_or_else(|| AuthError::StorageError("No store key in the keyring entry".to_string()))?;
+        Ok(Self {
+            active,
+            previous: keys.pop(),
+        })
+    }
+}
+
+// One store entry converted in memory, with the value it replaces for rollback
+struct Rewrapped {
+    key: String,
+    namespace: StoreNamespace,
+    previous: serde_json::Value,
+    sealed: serde_json::Value,
+}
+
+// XChaCha20-Poly1305 with a random nonce prepended to the ciphertext
+fn aead_seal(key: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, AuthError> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| AuthError::CryptoError(e.to_string()))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|e| AuthError::CryptoError(e.to_string()))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+fn aead_open(key: &[u8], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, AuthError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(AuthError::CryptoError("Sealed value is truncated".to_string()));
+    }
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| AuthError::CryptoError(e.to_string()))?;
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| AuthError::CryptoError("Sealed value failed authentication".to_string()))
+}
+
+fn xor(data: &[u8], key: &[u8]) -> Vec<u8> {
+    data.iter()
+        .zip(key.iter().cycle())
+        .map(|(d, k)| d ^ k)
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotationReport {
+    pub previous_version: u32,
+    pub version: u32,
+    pub sessions_rewrapped: usize,
+    pub secrets_rewrapped: usize,
+    pub rotated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SweepStats {
+    pub scanned: usize,
+    pub expired_sessions: usize,
+    pub stale_challenges: usize,
+    // Entries without expiry metadata. Sessions have always been written with a ten-minute
+    // expiry, so an undated one predates the envelope format and is long expired; purged.
+    pub undated: usize,
+    pub swept_at: Option<DateTime<Utc>>,
+}
+
+impl SweepStats {
+    pub fn purged(&self) -> usize {
+        self.expired_sessions + self.stale_challenges + self.undated
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceInfo {
     pub device_id: String,
@@ -68,110 +434,610 @@ pub struct DeviceInfo {
     pub user_agent: Option<String>,
 }
 
+// Argon2 cost parameters, persisted because changing them changes every derived key
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct KdfParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl KdfParams {
+    // What `Argon2::default()` used before parameters were persisted
+    fn legacy() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+
+    // Rejects records Argon2 would refuse, not only ones of the wrong shape
+    fn from_value(value: serde_json::Value) -> Result<Self, AuthError> {
+        let params: Self = serde_json::from_value(value).map_err(|e| AuthError::CryptoError(format!("Stored value is malformed: {}", e)))?;
+        Params::new(params.m_cost, params.t_cost, params.p_cost, None)
+            .map_err(|e| AuthError::CryptoError(e.to_string()))?;
+        Ok(params)
+    }
+
+    // Raises the time cost until one derivation takes KDF_TARGET_LATENCY on this host,
+    // never going below the legacy parameters
+    fn calibrate(salt: &str) -> Self {
+        let mut params = Self::legacy();
+        loop {
+            let started = Instant::now();
+            if derive_with(salt, params, b"symlog-kdf-calibration").is_err() {
+                return Self::legacy();
+            }
+            let elapsed = started.elapsed();
+            if elapsed >= KDF_TARGET_LATENCY || params.t_cost >= KDF_MAX_T_COST {
+                return params;
+            }
+            let scaled = params.t_cost as f64 * KDF_TARGET_LATENCY.as_secs_f64() / elapsed.as_secs_f64().max(0.001);
+            params.t_cost = (scaled as u32).clamp(params.t_cost + 1, KDF_MAX_T_COST);
+        }
+    }
+}
+
+// Parse key material records exactly as AuthManager::new will, for the startup check
+pub(crate) fn check_kdf_params(value: &serde_json::Value) -> Result<(), AuthError> {
+    KdfParams::from_value(value.clone()).map(|_| ())
+}
+
+pub(crate) fn check_legacy_store_key(value: &serde_json::Value) -> Result<(), AuthError> {
+    StoreKey::from_legacy_value(value.clone()).map(|_| ())
+}
+
+fn derive_with(salt: &str, params: KdfParams, passphrase: &[u8]) -> Result<Zeroizing<Vec<u8>>, AuthError> {
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, None)
+        .map_err(|e| AuthError::CryptoError(e.to_string()))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let salt = SaltString::from_b64(salt).map_err(|e| AuthError::CryptoError(e.to_string()))?;
+
+    let password_hash = argon2
+        .hash_password(passphrase, &salt)
+        .map_err(|e| AuthError::CryptoError(e.to_string()))?;
+    let hash = password_hash
+        .hash
+        .ok_or_else(|| AuthError::CryptoError("Argon2 produced no output".to_string()))?;
+    Ok(Zeroizing::new(hash.as_bytes().to_vec()))
+}
+
+// Most recently used derived keys, looked up by a digest of the passphrase
+#[derive(Default)]
+struct KeyCache {
+    entries: VecDeque<([u8; 32], Zeroizing<Vec<u8>>)>,
+}
+
+impl KeyCache {
+    fn get(&mut self, id: &[u8; 32]) -> Option<Zeroizing<Vec<u8>>> {
+        let position = self.entries.iter().position(|(entry_id, _)| entry_id == id)?;
+        let entry = self.entries.remove(position)?;
+        let key = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(key)
+    }
+
+    fn insert(&mut self, id: [u8; 32], key: Zeroizing<Vec<u8>>) {
+        self.entries.retain(|(entry_id, _)| *entry_id != id);
+        self.entries.push_front((id, key));
+        self.entries.truncate(KEY_CACHE_CAPACITY);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
 pub struct AuthManager {
-    store: Store<tauri::Wry>,
+    store: Arc<Store<tauri::Wry>>,
     key_derivation_salt: String,
+    kdf_params: KdfParams,
+    key_cache: Mutex<KeyCache>,
+    key_storage: KeyStorage,
+    // Held for writing across a rotation so no envelope is sealed with a retiring key
+    store_keys: RwLock<StoreKeys>,
+    // `state` of each sign-in started here, until its callback arrives or it expires
+    pending_states: Mutex<HashMap<String, DateTime<Utc>>>,
 }
 
 impl AuthManager {
     pub fn new(app: &AppHandle) -> Result<Self, AuthError> {
         let store = app
-            .store("auth.json")
+            .store(AUTH_STORE)
             .map_err(|e| AuthError::StorageError(e.to_string()))?;
         
+        if store.is_empty() {
+            store.set(SCHEMA_VERSION_KEY, migrations::current_version(AUTH_STORE));
+        }
+
         // Generate or retrieve a persistent salt for key derivation
+        let fresh_install = store.get(StoreNamespace::KeyMaterial.key("derivation_salt")).is_none();
         let key_derivation_salt = Self::get_or_create_salt(&store)?;
+        let kdf_params = Self::get_or_create_kdf_params(&store, &key_derivation_salt, fresh_install)?;
+        let key_storage = KeyStorage::open(app, STORE_KEY_ACCOUNT)?;
+        let store_keys = Self::load_store_keys(&store, &key_storage)?;
         
         Ok(Self {
             store,
             key_derivation_salt,
+            kdf_params,
+            key_cache: Mutex::new(KeyCache::default()),
+            key_storage,
+            store_keys: RwLock::new(store_keys),
+            pending_states: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // Existing installs keep the parameters their data was encrypted with
+    fn get_or_create_kdf_params(
+        store: &Store<tauri::Wry>,
+        salt: &str,
+        fresh_install: bool,
+    ) -> Result<KdfParams, AuthError> {
+        let key = StoreNamespace::KeyMaterial.key("kdf_params");
+        if let Some(params) = store.get(&key) {
+            return KdfParams::from_value(params);
+        }
+
+        let params = if fresh_install {
+            let params = KdfParams::calibrate(salt);
+            log::info!("Calibrated Argon2 parameters: {:?}", params);
+            params
+        } else {
+            KdfParams::legacy()
+        };
+        store.set(key, json!(params));
+        store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
+        Ok(params)
+    }
+
+    // Keys come from the keyring. An auth.json from before that still carries its key in
+    // plaintext; the key is imported as the previous one, every entry is re-wrapped under
+    // a fresh AEAD key and the record is removed from the file.
+    fn load_store_keys(store: &Store<tauri::Wry>, storage: &KeyStorage) -> Result<StoreKeys, AuthError> {
+        let legacy_record = StoreNamespace::KeyMaterial.key("store_key");
+        let mut keys = match storage.load()? {
+            Some(secret) => StoreKeys::from_secret(&secret)?,
+            // Sealed entries mean a key existed; a new one would only orphan them
+            None if store.get(&legacy_record).is_none() && Self::has_sealed_entries(store) => {
+                return Err(AuthError::KeyUnavailable(
+                    "the auth store holds sealed entries but key storage has no store key".to_string(),
+                ))
+            }
+            None => {
+                let legacy = store.get(&legacy_record).map(StoreKey::from_legacy_value).transpose()?;
+                let keys = StoreKeys {
+                    active: StoreKey::generate(legacy.as_ref().map_or(1, |key| key.version + 1)),
+                    previous: legacy,
+                };
+                storage.save(&keys.to_secret()?)?;
+                keys
+            }
+        };
+
+        if store.get(&legacy_record).is_none() {
+            return Ok(keys);
+        }
+        let rewrapped = Self::rewrap_entries(store, &keys, &keys.active)?;
+        for entry in &rewrapped {
+            store.set(entry.key.clone(), entry.sealed.clone());
+        }
+        store.delete(&legacy_record);
+        store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
+        log::info!(
+            "Moved the auth store key out of auth.json; {} entries re-wrapped under v{}",
+            rewrapped.len(),
+            keys.active.version
+        );
+
+        keys.previous = None;
+        if let Err(e) = storage.save(&keys.to_secret()?) {
+            log::warn!("Failed to drop the imported store key from the keyring: {}", e);
+        }
+        Ok(keys)
+    }
+
+    fn has_sealed_entries(store: &Store<tauri::Wry>) -> bool {
+        store.keys().iter().any(|key| {
+            matches!(
+                StoreNamespace::of(key),
+                Some(StoreNamespace::Session | StoreNamespace::Secret)
+            )
         })
     }
 
     fn get_or_create_salt(store: &Store<tauri::Wry>) -> Result<String, AuthError> {
-        if let Some(salt) = store.get("key_derivation_salt") {
-            Ok(salt.as_str().unwrap_or_default().to_string())
+        let key = StoreNamespace::KeyMaterial.key("derivation_salt");
+        if let Some(salt) = store.get(&key) {
+            salt.as_str()
+                .map(str::to_string)
+                .ok_or_else(|| AuthError::StorageError(format!("{} is not a string", key)))
         } else {
             let salt = SaltString::generate(&mut OsRng);
             let salt_str = salt.to_string();
-            store
-                .set("key_derivation_salt", serde_json::Value::String(salt_str.clone()))
-                .map_err(|e| AuthError::StorageError(e.to_string()))?;
+            store.set(key, serde_json::Value::String(salt_str.clone()));
             store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
             Ok(salt_str)
         }
     }
 
-    fn derive_key(&self, password: &str) -> Result<Vec<u8>, AuthError> {
-        let argon2 = Argon2::default();
-        let salt = SaltString::from_b64(&self.key_derivation_salt)
-            .map_err(|e| AuthError::CryptoError(e.to_string()))?;
-        
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map_err(|e| AuthError::CryptoError(e.to_string()))?;
+    pub fn install_salt(&self) -> &str {
+        &self.key_derivation_salt
+    }
+
+    // Argon2 runs on the blocking pool so auth commands never stall the async executor
+    async fn derive_key(&self, passphrase: &str) -> Result<Zeroizing<Vec<u8>>, AuthError> {
+        let id: [u8; 32] = Sha256::digest(passphrase.as_bytes()).into();
+        if let Some(key) = self.key_cache.lock().unwrap().get(&id) {
+            return Ok(key);
+        }
+
+        let salt = self.key_derivation_salt.clone();
+        let params = self.kdf_params;
+        let passphrase = Zeroizing::new(passphrase.as_bytes().to_vec());
+        let key = tauri::async_runtime::spawn_blocking(move || derive_with(&salt, params, &passphrase))
+            .await
+            .map_err(|e| AuthError::CryptoError(e.to_string()))??;
+
+        self.key_cache.lock().unwrap().insert(id, key.clone());
+        Ok(key)
+    }
+
+    async fn encrypt_value(&self, plaintext: &[u8], passphrase: &str) -> Result<String, AuthError> {
+        let key = self.derive_key(passphrase).await?;
+        let sealed = aead_seal(&key, &[], plaintext)?;
+        Ok(format!("{}{}", SEALED_PREFIX, general_purpose::STANDARD.encode(sealed)))
+    }
+
+    async fn decrypt_value(&self, encoded: &str, passphrase: &str) -> Result<Vec<u8>, AuthError> {
+        let key = self.derive_key(passphrase).await?;
+        let (sealed, encoded) = match encoded.strip_prefix(SEALED_PREFIX) {
+            Some(encoded) => (true, encoded),
+            None => (false, encoded),
+        };
+        let encrypted = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| AuthError::CryptoError(format!("Stored value is malformed: {}", e)))?;
         
-        Ok(password_hash.hash.unwrap().as_bytes().to_vec())
+        if sealed {
+            aead_open(&key, &[], &encrypted)
+        } else {
+            // Written before values were sealed; re-sealed the next time they are stored
+            Ok(xor(&encrypted, &key))
+        }
+    }
+
+    // The store entry's name is bound in as associated data, so a wrapped payload cannot
+    // be moved to another entry
+    fn wrap(store_key: &StoreKey, entry: &str, inner: &str) -> Result<String, AuthError> {
+        let sealed = aead_seal(&store_key.key, entry.as_bytes(), inner.as_bytes())?;
+        Ok(general_purpose::STANDARD.encode(sealed))
+    }
+
+    fn unwrap(keys: &StoreKeys, entry: &str, envelope: &Envelope) -> Result<String, AuthError> {
+        if envelope.key_version == 0 {
+            return Ok(envelope.payload.clone());
+        }
+        let store_key = keys.find(envelope.key_version).ok_or_else(|| {
+            AuthError::CryptoError(format!(
+                "Value is sealed with store key v{}, active key is v{}",
+                envelope.key_version, keys.active.version
+            ))
+        })?;
+        let wrapped = general_purpose::STANDARD
+            .decode(&envelope.payload)
+            .map_err(|e| AuthError::CryptoError(format!("Stored value is malformed: {}", e)))?;
+        let inner = if store_key.legacy {
+            xor(&wrapped, &store_key.key)
+        } else {
+            aead_open(&store_key.key, entry.as_bytes(), &wrapped)?
+        };
+        String::from_utf8(inner).map_err(|e| AuthError::CryptoError(e.to_string()))
+    }
+
+    // Converts every session and secret not yet wrapped with `target`; nothing is written
+    fn rewrap_entries(
+        store: &Store<tauri::Wry>,
+        keys: &StoreKeys,
+        target: &StoreKey,
+    ) -> Result<Vec<Rewrapped>, AuthError> {
+        let mut rewrapped = Vec::new();
+        for (key, value) in store.entries() {
+            let Some(namespace @ (StoreNamespace::Session | StoreNamespace::Secret)) = StoreNamespace::of(&key) else {
+                continue;
+            };
+            let mut envelope = Envelope::from_stored(value.clone())?;
+            if envelope.key_version == target.version {
+                continue;
+            }
+            let inner = Self::unwrap(keys, &key, &envelope)?;
+            envelope.key_version = target.version;
+            envelope.payload = Self::wrap(target, &key, &inner)?;
+            let sealed = serde_json::to_value(&envelope).map_err(|e| AuthError::StorageError(e.to_string()))?;
+            rewrapped.push(Rewrapped {
+                key,
+                namespace,
+                previous: value,
+                sealed,
+            });
+        }
+        Ok(rewrapped)
+    }
+
+    // Encrypts under the passphrase, wraps with the active store key and writes the envelope
+    async fn seal(
+        &self,
+        key: String,
+        plaintext: &[u8],
+        passphrase: &str,
+        expires_at: Option<DateTime<Utc>>,
+        pkce_expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), AuthError> {
+        let inner = self.encrypt_value(plaintext, passphrase).await?;
+        let keys = self.store_keys.read().unwrap();
+        let envelope = Envelope {
+            key_version: keys.active.version,
+            expires_at,
+            pkce_expires_at,
+            payload: Self::wrap(&keys.active, &key, &inner)?,
+        };
+        let value = serde_json::to_value(&envelope).map_err(|e| AuthError::StorageError(e.to_string()))?;
+        self.store.set(key, value);
+        self.store.save().map_err(|e| AuthError::StorageError(e.to_string()))
     }
 
-    pub fn store_session_encrypted(&self, session: &AuthSession, passphrase: &str) -> Result<(), AuthError> {
-        let key = self.derive_key(passphrase)?;
+    async fn open(&self, key: &str, envelope: &Envelope, passphrase: &str) -> Result<Vec<u8>, AuthError> {
+        let inner = Self::unwrap(&self.store_keys.read().unwrap(), key, envelope)?;
+        self.decrypt_value(&inner, passphrase).await
+    }
+
+    pub async fn store_session_encrypted(&self, session: &AuthSession, passphrase: &str) -> Result<(), AuthError> {
         let session_json = serde_json::to_string(session)
             .map_err(|e| AuthError::StorageError(e.to_string()))?;
+        self.seal(
+            StoreNamespace::Session.key(&session.id),
+            session_json.as_bytes(),
+            passphrase,
+            Some(session.expires_at),
+            session.pkce.as_ref().map(|pkce| pkce.expires_at),
+        )
+        .await
+    }
+
+    pub async fn retrieve_session_encrypted(&self, session_id: &str, passphrase: &str) -> Result<Option<AuthSession>, AuthError> {
+        let key = StoreNamespace::Session.key(session_id);
+        let Some(stored) = self.store.get(&key) else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+
+        let envelope = Envelope::from_stored(stored)?;
+        if envelope.is_expired(now) {
+            self.clear_session(session_id)?;
+            return Err(AuthError::ExpiredCode);
+        }
+
+        let decrypted = self.open(&key, &envelope, passphrase).await?;
+        let session_json = String::from_utf8(decrypted)
+            .map_err(|e| AuthError::StorageError(e.to_string()))?;
         
-        // Simple XOR encryption (in production, use AES-GCM or similar)
-        let encrypted = self.xor_encrypt(session_json.as_bytes(), &key);
-        let encoded = general_purpose::STANDARD.encode(&encrypted);
-        
-        self.store
-            .set(&format!("session_{}", session.id), serde_json::Value::String(encoded))
+        let session: AuthSession = serde_json::from_str(&session_json)
             .map_err(|e| AuthError::StorageError(e.to_string()))?;
+
+        let challenge_expired = session.pkce.as_ref().is_some_and(|pkce| pkce.expires_at <= now);
+        if session.expires_at <= now || challenge_expired {
+            self.clear_session(session_id)?;
+            return Err(AuthError::ExpiredCode);
+        }
         
-        self.store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
-        Ok(())
+        Ok(Some(session))
     }
 
-    pub fn retrieve_session_encrypted(&self, session_id: &str, passphrase: &str) -> Result<Option<AuthSession>, AuthError> {
-        let key = self.derive_key(passphrase)?;
-        
-        if let Some(encrypted_data) = self.store.get(&format!("session_{}", session_id)) {
-            let encoded = encrypted_data.as_str().ok_or_else(|| {
-                AuthError::StorageError("Invalid session data format".to_string())
-            })?;
-            
-            let encrypted = general_purpose::STANDARD
-                .decode(encoded)
-                .map_err(|e| AuthError::StorageError(e.to_string()))?;
-            
-            let decrypted = self.xor_encrypt(&encrypted, &key);
-            let session_json = String::from_utf8(decrypted)
-                .map_err(|e| AuthError::StorageError(e.to_string()))?;
-            
-            let session: AuthSession = serde_json::from_str(&session_json)
-                .map_err(|e| AuthError::StorageError(e.to_string()))?;
-            
-            Ok(Some(session))
-        } else {
-            Ok(None)
+    // Purges pending sessions whose session or PKCE challenge has expired, and any without
+    // an expiry at all
+    pub fn sweep_expired(&self) -> Result<SweepStats, AuthError> {
+        let now = Utc::now();
+        let mut stats = SweepStats {
+            swept_at: Some(now),
+            ..SweepStats::default()
+        };
+
+        for (key, value) in self.store.entries() {
+            if StoreNamespace::of(&key) != Some(StoreNamespace::Session) {
+                continue;
+            }
+            stats.scanned += 1;
+
+            let envelope = match Envelope::from_stored(value) {
+                Ok(envelope) => envelope,
+                Err(e) => {
+                    log::warn!("Dropping unreadable pending session {}: {}", key, e);
+                    stats.undated += 1;
+                    self.store.delete(&key);
+                    continue;
+                }
+            };
+            if envelope.expires_at.is_none() {
+                stats.undated += 1;
+            } else if envelope.expires_at.is_some_and(|expires_at| expires_at <= now) {
+                stats.expired_sessions += 1;
+            } else if envelope.challenge_expired(now) {
+                stats.stale_challenges += 1;
+            } else {
+                continue;
+            }
+            self.store.delete(&key);
+        }
+
+        if stats.purged() > 0 {
+            self.store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
+        }
+        Ok(stats)
+    }
+
+    // Device-bound key material, such as the DPoP signing key
+    pub async fn store_secret(&self, name: &str, secret: &[u8], passphrase: &str) -> Result<(), AuthError> {
+        self.seal(StoreNamespace::Secret.key(name), secret, passphrase, None, None)
+            .await
+    }
+
+    pub async fn retrieve_secret(&self, name: &str, passphrase: &str) -> Result<Option<Vec<u8>>, AuthError> {
+        let key = StoreNamespace::Secret.key(name);
+        match self.store.get(&key) {
+            Some(stored) => Ok(Some(self.open(&key, &Envelope::from_stored(stored)?, passphrase).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn has_secret(&self, name: &str) -> bool {
+        self.store.has(StoreNamespace::Secret.key(name))
+    }
+
+    pub fn clear_secret(&self, name: &str) -> Result<(), AuthError> {
+        self.store.delete(StoreNamespace::Secret.key(name));
+        self.store.save().map_err(|e| AuthError::StorageError(e.to_string()))
+    }
+
+    pub fn store_key_age(&self) -> chrono::Duration {
+        Utc::now() - self.store_keys.read().unwrap().active.created_at
+    }
+
+    // Re-wraps every session and secret under a fresh store key. All entries are converted
+    // in memory first. The new key is staged in the keyring next to the current one before
+    // the store is saved, so a crash in between leaves every entry readable, and a failed
+    // save restores the previous entries and keys.
+    pub fn rotate_store_key(&self, reason: &str) -> Result<RotationReport, AuthError> {
+        let mut keys = self.store_keys.write().unwrap();
+        let next = StoreKey::generate(keys.active.version + 1);
+        let rewrapped = Self::rewrap_entries(&self.store, &keys, &next)?;
+        let sessions_rewrapped = rewrapped
+            .iter()
+            .filter(|entry| entry.namespace == StoreNamespace::Session)
+            .count();
+        let report = RotationReport {
+            previous_version: keys.active.version,
+            version: next.version,
+            sessions_rewrapped,
+            secrets_rewrapped: rewrapped.len() - sessions_rewrapped,
+            rotated_at: next.created_at,
+        };
+
+        let staged = StoreKeys {
+            active: next,
+            previous: Some(keys.active.clone()),
+        };
+        self.key_storage.save(&staged.to_secret()?)?;
+        for entry in &rewrapped {
+            self.store.set(entry.key.clone(), entry.sealed.clone());
+        }
+
+        if let Err(e) = self.store.save() {
+            for entry in rewrapped {
+                self.store.set(entry.key, entry.previous);
+            }
+            if let Err(rollback) = self.store.save() {
+                log::error!("Failed to persist store key rollback: {}", rollback);
+            }
+            if let Err(rollback) = keys.to_secret().and_then(|secret| self.key_storage.save(&secret)) {
+                log::error!("Failed to restore the previous store key: {}", rollback);
+            }
+            return Err(AuthError::StorageError(e.to_string()));
+        }
+
+        *keys = StoreKeys {
+            active: staged.active,
+            previous: None,
+        };
+        if let Err(e) = keys.to_secret().and_then(|secret| self.key_storage.save(&secret)) {
+            log::warn!("Failed to drop the retired store key from the keyring: {}", e);
         }
+        log::info!(
+            "Rotated auth store key v{} -> v{} ({}): {} sessions, {} secrets",
+            report.previous_version,
+            report.version,
+            reason,
+            report.sessions_rewrapped,
+            report.secrets_rewrapped
+        );
+        Ok(report)
     }
 
-    fn xor_encrypt(&self, data: &[u8], key: &[u8]) -> Vec<u8> {
-        data.iter()
-            .zip(key.iter().cycle())
-            .map(|(d, k)| d ^ k)
-            .collect()
+    pub fn expect_callback(&self, state: &str, expires_at: DateTime<Utc>) {
+        let mut pending = self.pending_states.lock().unwrap();
+        pending.retain(|_, expires_at| *expires_at > Utc::now());
+        pending.insert(state.to_string(), expires_at);
+    }
+
+    // Looks without consuming, e.g. to let an expected callback past the rate limit
+    pub fn is_pending_state(&self, state: &str) -> bool {
+        let pending = self.pending_states.lock().unwrap();
+        pending
+            .iter()
+            .any(|(pending_state, expires_at)| constant_time_eq(pending_state, state) && *expires_at > Utc::now())
+    }
+
+    // One-shot: a state is consumed by the first callback that carries it
+    pub fn take_pending_state(&self, state: &str) -> bool {
+        let mut pending = self.pending_states.lock().unwrap();
+        let matched = pending
+            .keys()
+            .find(|pending_state| constant_time_eq(pending_state, state))
+            .cloned();
+        match matched.and_then(|key| pending.remove(&key)) {
+            Some(expires_at) => expires_at > Utc::now(),
+            None => false,
+        }
     }
 
     pub fn clear_session(&self, session_id: &str) -> Result<(), AuthError> {
-        self.store.delete(&format!("session_{}", session_id));
+        self.store.delete(StoreNamespace::Session.key(session_id));
         self.store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
         Ok(())
     }
 
-    pub fn clear_all_sessions(&self) -> Result<(), AuthError> {
+    pub fn clear_namespace(&self, namespace: StoreNamespace) -> Result<usize, AuthError> {
+        let keys: Vec<String> = self
+            .store
+            .keys()
+            .into_iter()
+            .filter(|key| StoreNamespace::of(key) == Some(namespace))
+            .collect();
+        for key in &keys {
+            self.store.delete(key);
+        }
+        self.store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
+        Ok(keys.len())
+    }
+
+    // Unexpired sign-in sessions; unreadable entries count, so the vault errs on locked
+    pub fn has_stored_session(&self) -> bool {
+        let now = Utc::now();
+        self.store.entries().into_iter().any(|(key, value)| {
+            StoreNamespace::of(&key) == Some(StoreNamespace::Session)
+                && Envelope::from_stored(value).map_or(true, |envelope| !envelope.is_expired(now))
+        })
+    }
+
+    // Signs out locally; the salt, device identity and device secrets survive
+    pub fn clear_all_sessions(&self) -> Result<usize, AuthError> {
+        self.clear_namespace(StoreNamespace::Session)
+    }
+
+    // Derived keys are recomputed from passphrases on next use
+    pub fn forget_keys(&self) {
+        self.key_cache.lock().unwrap().clear();
+    }
+
+    // Drops everything, including the salt every encrypted value depends on. The
+    // in-memory salt and device key are stale afterwards, so the app must restart.
+    pub fn factory_reset(&self) -> Result<(), AuthError> {
+        self.key_cache.lock().unwrap().clear();
         self.store.clear();
+        self.store.set(SCHEMA_VERSION_KEY, migrations::current_version(AUTH_STORE));
         self.store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
+        self.key_storage.delete()?;
+        log::warn!("Auth store was factory reset");
         Ok(())
     }
 }
@@ -235,10 +1101,9 @@ fn constant_time_eq(a: &str, b: &str) -> bool {
 // Tauri commands
 #[command]
 pub async fn generate_auth_session(
-    device_info: DeviceInfo,
-    app: AppHandle,
     auth_manager: State<'_, AuthManager>,
-) -> Result<AuthSession, AuthError> {
+    device_manager: State<'_, DeviceManager>,
+) -> Result<AuthSession, CommandError> {
     let session_id = Uuid::new_v4().to_string();
     let state = generate_secure_random_string(32);
     let pkce = generate_pkce_challenge()?;
@@ -253,12 +1118,13 @@ pub async fn generate_auth_session(
         state,
         created_at: Utc::now(),
         expires_at: Utc::now() + chrono::Duration::minutes(10),
-        device_info,
+        device_info: device_manager.device_info(),
     };
     
     // Store session with device-specific encryption
     let passphrase = format!("{}-{}", session.device_info.device_id, session.state);
-    auth_manager.store_session_encrypted(&session, &passphrase)?;
+    auth_manager.store_session_encrypted(&session, &passphrase).await?;
+    auth_manager.expect_callback(&session.state, session.expires_at);
     
     Ok(session)
 }
@@ -267,7 +1133,8 @@ pub async fn generate_auth_session(
 pub async fn handle_auth_callback(
     url: String,
     auth_manager: State<'_, AuthManager>,
-) -> Result<AuthSession, AuthError> {
+    device_manager: State<'_, DeviceManager>,
+) -> Result<AuthSession, CommandError> {
     let parsed_url = Url::parse(&url).map_err(|e| AuthError::InvalidUrl(e.to_string()))?;
     
     // Extract parameters from callback URL
@@ -293,12 +1160,7 @@ pub async fn handle_auth_callback(
         state: state.clone(),
         created_at: Utc::now(),
         expires_at: Utc::now() + chrono::Duration::hours(24),
-        device_info: DeviceInfo {
-            device_id: "desktop".to_string(),
-            device_name: "Desktop App".to_string(),
-            platform: std::env::consts::OS.to_string(),
-            user_agent: None,
-        },
+        device_info: device_manager.device_info(),
     };
     
     Ok(session)
@@ -308,24 +1170,177 @@ pub async fn handle_auth_callback(
 pub async fn clear_auth_session(
     session_id: String,
     auth_manager: State<'_, AuthManager>,
-) -> Result<(), AuthError> {
-    auth_manager.clear_session(&session_id)
+) -> Result<(), CommandError> {
+    auth_manager.clear_session(&session_id).map_err(CommandError::from)
 }
 
 #[command]
 pub async fn clear_all_auth_sessions(
     auth_manager: State<'_, AuthManager>,
-) -> Result<(), AuthError> {
-    auth_manager.clear_all_sessions()
+) -> Result<(), CommandError> {
+    auth_manager.clear_all_sessions()?;
+    Ok(())
+}
+
+// Revokes every backend session for the user first; local data is only removed once
+// the backend has confirmed, so a failed call leaves the user signed in here as well
+#[command]
+pub async fn sign_out_everywhere(
+    access_token: String,
+    auth_manager: State<'_, AuthManager>,
+    validator: State<'_, JwtValidator>,
+    device_key: State<'_, DeviceKey>,
+    backend: State<'_, BackendClient>,
+) -> Result<SignOutSummary, CommandError> {
+    let claims = validator.verify(&access_token, &device_key, &backend).await?;
+    let result: RevokeAllResult = backend
+        .mutation(
+            "sessions:revokeAllUserSessions",
+            json!({ "userId": claims.user_id, "reason": "user_sign_out_everywhere" }),
+            &access_token,
+            &device_key,
+        )
+        .await?;
+
+    let local_sessions_cleared = auth_manager.clear_all_sessions()?;
+    log::info!(
+        "Signed out everywhere: {} backend sessions revoked, {} local sessions cleared",
+        result.sessions_revoked,
+        local_sessions_cleared
+    );
+    Ok(SignOutSummary {
+        sessions_revoked: result.sessions_revoked,
+        local_sessions_cleared,
+    })
+}
+
+// Token requests go through the shell so they carry a DPoP proof of the device key. The
+// tokens that come back are bearer tokens: the backend does not bind them to the key yet.
+#[command]
+pub async fn exchange_auth_code(
+    auth_code: String,
+    code_verifier: String,
+    device_manager: State<'_, DeviceManager>,
+    device_key: State<'_, DeviceKey>,
+    backend: State<'_, BackendClient>,
+    validator: State<'_, JwtValidator>,
+    vault: State<'_, Vault>,
+) -> Result<TokenGrant, CommandError> {
+    exchange_code(
+        &auth_code,
+        &code_verifier,
+        &device_manager.device_info(),
+        &device_key,
+        &backend,
+        &validator,
+        &vault,
+    )
+    .await
+    .map_err(CommandError::from)
+}
+
+pub(crate) async fn exchange_code(
+    auth_code: &str,
+    code_verifier: &str,
+    device: &DeviceInfo,
+    device_key: &DeviceKey,
+    backend: &BackendClient,
+    validator: &JwtValidator,
+    vault: &Vault,
+) -> Result<TokenGrant, AuthError> {
+    let exchange: CodeExchange = backend
+        .token_mutation(
+            "auth:validateAuthCode",
+            json!({
+                "authCode": auth_code,
+                "codeVerifier": code_verifier,
+                "deviceId": device.device_id,
+                "deviceName": device.device_name,
+                "deviceType": "desktop",
+                "platform": device.platform,
+                "userAgent": device.user_agent,
+            }),
+            device_key,
+        )
+        .await?;
+    let token = exchange.session.into_token()?;
+
+    // Signing in again is how a locked vault is opened when there is no token to hand
+    vault.unlock_with(&token, validator, device_key, backend).await?;
+    log::info!("Vault unlocked by sign-in");
+    Ok(TokenGrant {
+        user_id: exchange.user_id,
+        email: exchange.user_email,
+        wallet_address: exchange.wallet_address,
+        token,
+    })
+}
+
+#[command]
+pub async fn refresh_auth_token(
+    refresh_token: String,
+    device_manager: State<'_, DeviceManager>,
+    device_key: State<'_, DeviceKey>,
+    backend: State<'_, BackendClient>,
+) -> Result<AuthToken, CommandError> {
+    let tokens: IssuedTokens = backend
+        .token_mutation(
+            "refreshTokens:rotateRefreshToken",
+            json!({
+                "refreshToken": refresh_token,
+                "userAgent": device_manager.device_info().user_agent,
+            }),
+            &device_key,
+        )
+        .await?;
+    tokens.into_token().map_err(CommandError::from)
+}
+
+#[command]
+pub async fn factory_reset_auth_store(
+    confirmation: String,
+    app: AppHandle,
+    auth_manager: State<'_, AuthManager>,
+) -> Result<(), CommandError> {
+    if confirmation != FACTORY_RESET_CONFIRMATION {
+        return Err(AuthError::ConfirmationRequired(format!(
+            "type \"{}\" to confirm a factory reset",
+            FACTORY_RESET_CONFIRMATION
+        ))
+        .into());
+    }
+    auth_manager.factory_reset()?;
+    app.request_restart();
+    Ok(())
+}
+
+#[command]
+pub async fn sweep_expired_auth_sessions(
+    auth_manager: State<'_, AuthManager>,
+) -> Result<SweepStats, CommandError> {
+    auth_manager.sweep_expired().map_err(CommandError::from)
+}
+
+#[command]
+pub async fn rotate_auth_store_key(
+    reason: Option<String>,
+    auth_manager: State<'_, AuthManager>,
+) -> Result<RotationReport, CommandError> {
+    auth_manager
+        .rotate_store_key(reason.as_deref().unwrap_or("on demand"))
+        .map_err(CommandError::from)
 }
 
 #[command]
 pub async fn get_auth_session(
     session_id: String,
-    device_id: String,
     state: String,
     auth_manager: State<'_, AuthManager>,
-) -> Result<Option<AuthSession>, AuthError> {
-    let passphrase = format!("{}-{}", device_id, state);
-    auth_manager.retrieve_session_encrypted(&session_id, &passphrase)
+    device_manager: State<'_, DeviceManager>,
+) -> Result<Option<AuthSession>, CommandError> {
+    let passphrase = format!("{}-{}", device_manager.identity().device_id, state);
+    auth_manager
+        .retrieve_session_encrypted(&session_id, &passphrase)
+        .await
+        .map_err(CommandError::from)
 }
\ No newline at end of file
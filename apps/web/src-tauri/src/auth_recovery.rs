@@ -0,0 +1,189 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, State};
+use tauri_plugin_store::{resolve_store_path, StoreExt};
+use argon2::password_hash::SaltString;
+use chrono::{DateTime, Utc};
+use crate::auth::{self, AuthError, AuthManager, StoreNamespace, AUTH_STORE};
+use crate::device::DeviceManager;
+use crate::dpop::DeviceKey;
+use crate::error::CommandError;
+
+pub const AUTH_STORE_RECOVERED_EVENT: &str = "auth_store_recovered";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    pub reason: String,
+    pub backup_path: PathBuf,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+// Holds the report from this launch so a webview that subscribed late can still show it
+pub struct RecoveryState {
+    report: Option<RecoveryReport>,
+}
+
+impl RecoveryState {
+    pub fn new(report: Option<RecoveryReport>) -> Self {
+        Self { report }
+    }
+}
+
+// Every key that later code parses without a fallback; a value of the wrong shape here
+// would otherwise turn into an empty salt or an unusable store key
+fn validate(bytes: &[u8]) -> Result<(), String> {
+    let value: serde_json::Value =
+        serde_json::from_slice(bytes).map_err(|e| format!("auth store is not valid JSON: {}", e))?;
+    let entries = value
+        .as_object()
+        .ok_or_else(|| "auth store is not a JSON object".to_string())?;
+
+    let salt_key = StoreNamespace::KeyMaterial.key("derivation_salt");
+    match entries.get(&salt_key) {
+        Some(serde_json::Value::String(salt)) => {
+            SaltString::from_b64(salt).map_err(|e| format!("{} is not a valid salt: {}", salt_key, e))?;
+        }
+        Some(_) => return Err(format!("{} is not a string", salt_key)),
+        None => {
+            // Encrypted values are useless without the salt they were derived with
+            let encrypted = entries.keys().any(|key| {
+                matches!(
+                    StoreNamespace::of(key),
+                    Some(StoreNamespace::Session | StoreNamespace::Secret)
+                )
+            });
+            if encrypted {
+                return Err(format!("encrypted entries exist but {} is missing", salt_key));
+            }
+        }
+    }
+
+    let checks: [(&str, fn(&serde_json::Value) -> Result<(), AuthError>); 2] = [
+        ("store_key", auth::check_legacy_store_key),
+        ("kdf_params", auth::check_kdf_params),
+    ];
+    for (name, check) in checks {
+        let key = StoreNamespace::KeyMaterial.key(name);
+        if let Some(record) = entries.get(&key) {
+            check(record).map_err(|e| format!("{} is unusable: {}", key, e))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn quarantine(path: &Path, reason: String) -> Result<RecoveryReport, AuthError> {
+    let quarantined_at = Utc::now();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| AUTH_STORE.to_string());
+    let backup_path = path.with_file_name(format!(
+        "{}.corrupt-{}",
+        file_name,
+        quarantined_at.format("%Y%m%dT%H%M%SZ")
+    ));
+
+    fs::rename(path, &backup_path).map_err(|e| AuthError::StorageError(e.to_string()))?;
+    log::error!(
+        "Quarantined corrupt auth store to {}: {}",
+        backup_path.display(),
+        reason
+    );
+
+    Ok(RecoveryReport {
+        reason,
+        backup_path,
+        quarantined_at,
+    })
+}
+
+// Must run before the store plugin first loads the auth store: the plugin silently
+// replaces an unparsable file with an empty store and overwrites it on the next save
+pub fn check_auth_store(app: &AppHandle) -> Result<Option<RecoveryReport>, AuthError> {
+    let path = resolve_store_path(app, AUTH_STORE).map_err(|e| AuthError::StorageError(e.to_string()))?;
+
+    // A file that cannot be read right now is not known to be corrupt
+    let problem = match fs::read(&path) {
+        Ok(bytes) => validate(&bytes).err(),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(AuthError::StorageError(format!("auth store could not be read: {}", e))),
+    };
+
+    match problem {
+        Some(reason) => quarantine(&path, reason).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn load(app: &AppHandle) -> Result<(AuthManager, DeviceManager, DeviceKey), AuthError> {
+    let auth_manager = AuthManager::new(app)?;
+    let device_manager = DeviceManager::new(app, &auth_manager)?;
+    let device_key = tauri::async_runtime::block_on(DeviceKey::load_or_create(app, &auth_manager, &device_manager))?;
+    Ok((auth_manager, device_manager, device_key))
+}
+
+// Only a value that cannot be opened or parsed proves the store is damaged. Everything
+// else (an I/O error, a keyring that is not answering) says nothing about the data, and
+// quarantining on it would throw away a healthy store.
+fn proves_corruption(error: &AuthError) -> bool {
+    matches!(error, AuthError::CryptoError(_))
+}
+
+// A keyring or disk that is slow to come up with the session gets one more chance
+const LOAD_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+// Some damage only shows while loading, e.g. a device secret sealed with a key the keyring
+// no longer holds. The store is then quarantined like a corrupt file and loaded once more,
+// empty. The plugin's cached copy is closed first so it is neither handed out again nor
+// saved over the file after it has been moved aside. Any other failure is retried once
+// and then stops startup, leaving the store untouched.
+pub fn load_or_recover(
+    app: &AppHandle,
+) -> Result<(AuthManager, DeviceManager, DeviceKey, Option<RecoveryReport>), AuthError> {
+    let mut error = match load(app) {
+        Ok((auth_manager, device_manager, device_key)) => {
+            return Ok((auth_manager, device_manager, device_key, None))
+        }
+        Err(e) => e,
+    };
+    if !proves_corruption(&error) {
+        log::warn!("Auth store failed to load, retrying: {}", error);
+        std::thread::sleep(LOAD_RETRY_DELAY);
+        error = match load(app) {
+            Ok((auth_manager, device_manager, device_key)) => {
+                return Ok((auth_manager, device_manager, device_key, None))
+            }
+            Err(e) => e,
+        };
+    }
+    if !proves_corruption(&error) {
+        log::error!("Auth store could not be loaded; leaving it in place: {}", error);
+        return Err(error);
+    }
+
+    log::error!("Auth store failed to load: {}", error);
+    if let Some(store) = app.get_store(AUTH_STORE) {
+        store.close_resource();
+    }
+    let path = resolve_store_path(app, AUTH_STORE).map_err(|e| AuthError::StorageError(e.to_string()))?;
+    let report = quarantine(&path, format!("auth store failed to load: {}", error))?;
+    let (auth_manager, device_manager, device_key) = load(app)?;
+    Ok((auth_manager, device_manager, device_key, Some(report)))
+}
+
+pub fn notify_recovery(app: &AppHandle, report: &RecoveryReport) {
+    if let Err(e) = app.emit(AUTH_STORE_RECOVERED_EVENT, report) {
+        log::error!("Failed to emit auth store recovery event: {}", e);
+    }
+}
+
+// Tauri commands
+#[command]
+pub async fn get_auth_store_recovery(
+    recovery: State<'_, RecoveryState>,
+) -> Result<Option<RecoveryReport>, CommandError> {
+    Ok(recovery.report.clone())
+}
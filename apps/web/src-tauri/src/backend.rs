@@ -0,0 +1,414 @@
+use std::time::Duration;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use crate::auth::AuthError;
+use crate::dpop::DeviceKey;
+
+// Runtime override, e.g. a local HTTP stand-in to exercise the shell's backend calls offline
+const CONVEX_URL_VAR: &str = "SYMLOG_CONVEX_URL";
+// The deployment the webview talks to (NEXT_PUBLIC_CONVEX_URL in vercel.json); a build
+// can point both at another one by setting that variable when compiling the shell
+const DEFAULT_CONVEX_URL: &str = match option_env!("NEXT_PUBLIC_CONVEX_URL") {
+    Some(url) => url,
+    None => "https://valiant-chihuahua-726.convex.cloud",
+};
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const DPOP_HEADER: &str = "DPoP";
+const DPOP_NONCE_HEADER: &str = "DPoP-Nonce";
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+enum ConvexResponse {
+    Success {
+        value: serde_json::Value,
+    },
+    Error {
+        #[serde(rename = "errorMessage")]
+        error_message: String,
+    },
+}
+
+// Calls Convex functions over the HTTP API on behalf of the shell. Every request carries
+// a DPoP proof from the device key, including token requests made before there is a token.
+// The backend still issues bearer tokens and reads them from the function arguments, so
+// no Authorization header is sent; the proof's `ath` names the token the call is about.
+pub struct BackendClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl BackendClient {
+    pub fn from_env() -> Self {
+        Self {
+            base_url: std::env::var(CONVEX_URL_VAR)
+                .ok()
+                .filter(|url| !url.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_CONVEX_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        kind: &str,
+        path: &str,
+        args: serde_json::Value,
+        access_token: Option<&str>,
+        device_key: &DeviceKey,
+    ) -> Result<T, AuthError> {
+        let url = format!("{}/api/{}", self.base_url, kind);
+        let body = json!({ "path": path, "args": args, "format": "json" });
+
+        // A server that wants a fresh nonce answers 401 with DPoP-Nonce; the proof is
+        // signed again with it, once (RFC 9449 section 8)
+        let mut nonce: Option<String> = None;
+        let response = loop {
+            let proof = device_key.proof("POST", &url, access_token, nonce.as_deref())?;
+            let response = self
+                .client
+                .post(&url)
+                .header(DPOP_HEADER, proof)
+                .timeout(REQUEST_TIMEOUT)
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+            let challenge = if nonce.is_none() && response.status() == StatusCode::UNAUTHORIZED {
+                response
+                    .headers()
+                    .get(DPOP_NONCE_HEADER)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            } else {
+                None
+            };
+            match challenge {
+                Some(challenge) => nonce = Some(challenge),
+                None => break response,
+            }
+        };
+
+        let response = response
+            .error_for_status()
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?
+            .json::<ConvexResponse>()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+        match response {
+            ConvexResponse::Success { value } => {
+                serde_json::from_value(value).map_err(|e| AuthError::NetworkError(e.to_string()))
+            }
+            ConvexResponse::Error { error_message } => Err(AuthError::NetworkError(error_message)),
+        }
+    }
+
+    pub async fn query<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        args: serde_json::Value,
+        access_token: &str,
+        device_key: &DeviceKey,
+    ) -> Result<T, AuthError> {
+        self.call("query", path, args, Some(access_token), device_key).await
+    }
+
+    pub async fn mutation<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        args: serde_json::Value,
+        access_token: &str,
+        device_key: &DeviceKey,
+    ) -> Result<T, AuthError> {
+        self.call("mutation", path, args, Some(access_token), device_key).await
+    }
+
+    // Code exchange and refresh: proof of the device key, but no access token yet
+    pub async fn token_mutation<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        args: serde_json::Value,
+        device_key: &DeviceKey,
+    ) -> Result<T, AuthError> {
+        self.call("mutation", path, args, None, device_key).await
+    }
+}
+
+// A local HTTP server standing in for the Convex deployment in tests
+#[cfg(test)]
+pub(crate) mod stand_in {
+    use std::sync::{Arc, Mutex};
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use super::BackendClient;
+
+    #[derive(Debug, Clone)]
+    pub struct Recorded {
+        pub path: String,
+        pub headers: Vec<(String, String)>,
+        pub body: Value,
+    }
+
+    impl Recorded {
+        pub fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        }
+
+        // The Convex function the request called, e.g. `sessions:getUserSessions`
+        pub fn function(&self) -> &str {
+            self.body["path"].as_str().unwrap_or_default()
+        }
+    }
+
+    pub struct Reply {
+        pub status: u16,
+        pub headers: Vec<(&'static str, String)>,
+        pub body: Value,
+    }
+
+    impl Reply {
+        pub fn value(value: Value) -> Self {
+            Self {
+                status: 200,
+                headers: Vec::new(),
+                body: json!({ "status": "success", "value": value }),
+            }
+        }
+    }
+
+    pub struct StandIn {
+        pub client: BackendClient,
+        pub url: String,
+        requests: Arc<Mutex<Vec<Recorded>>>,
+    }
+
+    impl StandIn {
+        pub fn requests(&self) -> Vec<Recorded> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    // Answers every request with whatever `handler` returns for it, one request per connection
+    pub async fn serve<F>(handler: F) -> StandIn
+    where
+        F: Fn(&Recorded) -> Reply + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let log = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let request = read_request(&mut socket).await;
+                let reply = handler(&request);
+                log.lock().unwrap().push(request);
+
+                let body = reply.body.to_string();
+                let mut head = format!(
+                    "HTTP/1.1 {} Stand-in\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n",
+                    reply.status,
+                    body.len()
+                );
+                for (name, value) in &reply.headers {
+                    head.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                head.push_str("\r\n");
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(body.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        StandIn {
+            client: BackendClient {
+                base_url: url.clone(),
+                client: reqwest::Client::new(),
+            },
+            url,
+            requests,
+        }
+    }
+
+    async fn read_request(socket: &mut TcpStream) -> Recorded {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let head_len = loop {
+            let read = socket.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "connection closed before the request head");
+            buffer.extend_from_slice(&chunk[..read]);
+            if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+
+        let head = String::from_utf8_lossy(&buffer[..head_len]).to_string();
+        let mut lines = head.split("\r\n");
+        let path = lines
+            .next()
+            .and_then(|line| line.split(' ').nth(1))
+            .unwrap_or_default()
+            .to_string();
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        let content_length = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        while buffer.len() < head_len + content_length {
+            let read = socket.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "connection closed before the request body");
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+        let body = serde_json::from_slice(&buffer[head_len..head_len + content_length]).unwrap_or(Value::Null);
+
+        Recorded { path, headers, body }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose, Engine as _};
+    use ed25519_dalek::{Signature, SigningKey, Verifier, VerifyingKey};
+    use serde_json::{json, Value};
+    use sha2::{Digest, Sha256};
+    use super::stand_in::{self, Recorded, Reply};
+    use super::*;
+
+    const ACCESS_TOKEN: &str = "header.payload.signature";
+    const NONCE: &str = "server-nonce-1";
+
+    fn device_key() -> DeviceKey {
+        DeviceKey::from_signing_key(SigningKey::from_bytes(&[7u8; 32]))
+    }
+
+    fn decode(segment: &str) -> Value {
+        serde_json::from_slice(&general_purpose::URL_SAFE_NO_PAD.decode(segment).unwrap()).unwrap()
+    }
+
+    // Checks a proof the way a resource server would (RFC 9449 section 4.3) and returns its claims
+    fn verify_proof(request: &Recorded, htu: &str, access_token: Option<&str>) -> Value {
+        let proof = request.header("DPoP").expect("request carries no DPoP proof");
+        let segments: Vec<&str> = proof.split('.').collect();
+        assert_eq!(segments.len(), 3);
+        let (header, claims) = (decode(segments[0]), decode(segments[1]));
+
+        assert_eq!(header["typ"], "dpop+jwt");
+        assert_eq!(header["alg"], "EdDSA");
+        assert_eq!(header["jwk"]["kty"], "OKP");
+        assert_eq!(header["jwk"]["crv"], "Ed25519");
+        let x: [u8; 32] = general_purpose::URL_SAFE_NO_PAD
+            .decode(header["jwk"]["x"].as_str().unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let signature = Signature::from_slice(&general_purpose::URL_SAFE_NO_PAD.decode(segments[2]).unwrap()).unwrap();
+        VerifyingKey::from_bytes(&x)
+            .unwrap()
+            .verify(format!("{}.{}", segments[0], segments[1]).as_bytes(), &signature)
+            .expect("proof signature does not verify against its jwk");
+
+        // The server identifies the device by the thumbprint of the proof's key
+        let canonical_jwk = format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, header["jwk"]["x"].as_str().unwrap());
+        let thumbprint = general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(canonical_jwk.as_bytes()));
+        assert_eq!(thumbprint, device_key().thumbprint());
+
+        assert_eq!(claims["htm"], "POST");
+        assert_eq!(claims["htu"], htu);
+        assert!(claims["jti"].as_str().is_some_and(|jti| !jti.is_empty()));
+        assert!((chrono::Utc::now().timestamp() - claims["iat"].as_i64().unwrap()).abs() < 60);
+        match access_token {
+            Some(token) => {
+                let ath = general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()));
+                assert_eq!(claims["ath"], ath);
+            }
+            None => assert!(claims.get("ath").is_none()),
+        }
+        // Tokens are bearer tokens passed as arguments, never as a DPoP-bound Authorization
+        assert_eq!(request.header("Authorization"), None);
+        claims
+    }
+
+    #[tokio::test]
+    async fn api_calls_carry_a_proof_bound_to_the_token_and_retry_with_a_nonce() {
+        let server = stand_in::serve(|request| {
+            let claims = decode(request.header("DPoP").unwrap().split('.').nth(1).unwrap());
+            if claims.get("nonce").is_none() {
+                return Reply {
+                    status: 401,
+                    headers: vec![("DPoP-Nonce", NONCE.to_string())],
+                    body: json!({ "error": "use_dpop_nonce" }),
+                };
+            }
+            Reply::value(json!({ "valid": true }))
+        })
+        .await;
+
+        let value: Value = server
+            .client
+            .query("sessions:validateAccessToken", json!({}), ACCESS_TOKEN, &device_key())
+            .await
+            .unwrap();
+        assert_eq!(value, json!({ "valid": true }));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let htu = format!("{}/api/query", server.url);
+        let first = verify_proof(&requests[0], &htu, Some(ACCESS_TOKEN));
+        let retried = verify_proof(&requests[1], &htu, Some(ACCESS_TOKEN));
+        assert!(first.get("nonce").is_none());
+        assert_eq!(retried["nonce"], NONCE);
+        assert_ne!(first["jti"], retried["jti"]);
+        assert_eq!(requests[1].path, "/api/query");
+        assert_eq!(requests[1].function(), "sessions:validateAccessToken");
+    }
+
+    #[tokio::test]
+    async fn token_requests_carry_a_proof_without_a_token() {
+        let server = stand_in::serve(|_| Reply::value(json!({ "ok": true }))).await;
+
+        let _: Value = server
+            .client
+            .token_mutation("refreshTokens:rotateRefreshToken", json!({ "refreshToken": "r" }), &device_key())
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        verify_proof(&requests[0], &format!("{}/api/mutation", server.url), None);
+    }
+
+    #[tokio::test]
+    async fn a_second_nonce_challenge_is_not_retried() {
+        let server = stand_in::serve(|_| Reply {
+            status: 401,
+            headers: vec![("DPoP-Nonce", NONCE.to_string())],
+            body: json!({ "error": "use_dpop_nonce" }),
+        })
+        .await;
+
+        let result: Result<Value, AuthError> = server
+            .client
+            .mutation("sessions:revokeSession", json!({}), ACCESS_TOKEN, &device_key())
+            .await;
+        assert!(matches!(result, Err(AuthError::NetworkError(_))));
+        assert_eq!(server.requests().len(), 2);
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use tauri::http::{Request, StatusCode};
+use tauri::{command, AppHandle, Manager, State, UriSchemeContext, UriSchemeResponder};
+use tauri_plugin_store::{Store, StoreExt};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use thiserror::Error;
+use crate::asset_protocol::{file_response, status_response};
+use crate::attachments::hex_digest;
+use crate::error::CommandError;
+use crate::migrations::{self, SCHEMA_VERSION_KEY};
+use crate::files::write_atomic;
+
+pub const BLOB_SCHEME: &str = "symlog-blob";
+pub const BLOB_INDEX_STORE: &str = "blobs.json";
+const DEFAULT_QUOTA_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+// Unreferenced blobs survive this long so a freshly stored blob can be attached before GC runs
+const ORPHAN_GRACE_MINUTES: i64 = 60;
+
+#[derive(Error, Debug)]
+pub enum BlobError {
+    #[error("Blob not found: {0}")]
+    NotFound(String),
+    #[error("Invalid blob hash: {0}")]
+    InvalidHash(String),
+    #[error("Blob of {0} bytes does not fit in the store quota")]
+    QuotaExceeded(u64),
+    #[error("Blob storage error: {0}")]
+    StorageError(String),
+}
+
+impl From<std::io::Error> for BlobError {
+    fn from(e: std::io::Error) -> Self {
+        BlobError::StorageError(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobEntry {
+    pub hash: String,
+    pub size: u64,
+    pub mime_type: String,
+    pub created_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+    pub refs: HashSet<String>,
+}
+
+impl BlobEntry {
+    pub fn url(&self) -> String {
+        blob_url(&self.hash)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlobStats {
+    pub blob_count: usize,
+    pub total_bytes: u64,
+    pub quota_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GcStats {
+    pub orphans_removed: usize,
+    pub evicted: usize,
+    pub stray_files_removed: usize,
+    pub bytes_freed: u64,
+}
+
+pub struct BlobStore {
+    root: PathBuf,
+    quota_bytes: u64,
+    store: Arc<Store<tauri::Wry>>,
+    // In-memory view of the index; the store is the durable copy
+    index: Mutex<HashMap<String, BlobEntry>>,
+    // Blobs read since the last save, whose new access time is not in the store yet
+    touched: Mutex<HashSet<String>>,
+}
+
+impl BlobStore {
+    pub fn new(app: &AppHandle) -> Result<Self, BlobError> {
+        let root = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| BlobError::StorageError(e.to_string()))?
+            .join("blobs");
+        fs::create_dir_all(&root)?;
+
+        let store = app
+            .store(BLOB_INDEX_STORE)
+            .map_err(|e| BlobError::StorageError(e.to_string()))?;
+        if store.is_empty() {
+            store.set(SCHEMA_VERSION_KEY, migrations::current_version(BLOB_INDEX_STORE));
+        }
+
+        let mut index = HashMap::new();
+        for (key, value) in store.entries() {
+            if !key.starts_with("blob_") {
+                continue;
+            }
+            match serde_json::from_value::<BlobEntry>(value) {
+                Ok(entry) => {
+                    index.insert(entry.hash.clone(), entry);
+                }
+                Err(e) => log::warn!("Dropping unreadable blob index entry {}: {}", key, e),
+            }
+        }
+
+        Ok(Self {
+            root,
+            quota_bytes: DEFAULT_QUOTA_BYTES,
+            store,
+            index: Mutex::new(index),
+            touched: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        // Fan out by the first byte so no single directory grows unbounded
+        self.root.join(&hash[..2]).join(&hash[2..])
+    }
+
+    fn persist(&self, entry: &BlobEntry) -> Result<(), BlobError> {
+        let value = serde_json::to_value(entry).map_err(|e| BlobError::StorageError(e.to_string()))?;
+        self.store.set(format!("blob_{}", entry.hash), value);
+        Ok(())
+    }
+
+    // Access times only steer eviction, so reads do not write the index themselves; their
+    // times are written here, with the next change or GC run
+    fn save(&self) -> Result<(), BlobError> {
+        let touched: Vec<String> = self.touched.lock().unwrap().drain().collect();
+        {
+            let index = self.index.lock().unwrap();
+            for hash in touched {
+                if let Some(entry) = index.get(&hash) {
+                    self.persist(entry)?;
+                }
+            }
+        }
+        self.store.save().map_err(|e| BlobError::StorageError(e.to_string()))
+    }
+
+    pub fn put(&self, bytes: &[u8], mime_type: &str, owner: Option<&str>) -> Result<BlobEntry, BlobError> {
+        let size = bytes.len() as u64;
+        if size > self.quota_bytes {
+            return Err(BlobError::QuotaExceeded(size));
+        }
+
+        let hash = hex_digest(bytes);
+        let now = Utc::now();
+        let entry = {
+            let mut index = self.index.lock().unwrap();
+            let path = self.blob_path(&hash);
+
+            // Content addressing makes a re-put of known content a ref/touch only; a known
+            // hash whose file has gone missing only needs the file written again
+            let known = index.contains_key(&hash);
+            if !(known && path.exists()) {
+                // Room is made before writing, so a blob that cannot fit is never stored
+                let incoming = if known { 0 } else { size };
+                self.make_room(&mut index, incoming)?;
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                write_atomic(&path, bytes).map_err(|e| BlobError::StorageError(e.to_string()))?;
+            }
+
+            let entry = upsert(&mut index, &hash, size, mime_type, now);
+            if let Some(owner) = owner {
+                entry.refs.insert(owner.to_string());
+            }
+            let entry = entry.clone();
+            self.persist(&entry)?;
+            entry
+        };
+
+        self.save()?;
+        Ok(entry)
+    }
+
+    // Indexed and still on disk; not an access
+    pub fn contains(&self, hash: &str) -> bool {
+        validate_hash(hash).is_ok()
+            && self.index.lock().unwrap().contains_key(hash)
+            && self.blob_path(hash).is_file()
+    }
+
+    // Resolves a blob to its file on disk, counting as an access for LRU purposes
+    pub fn locate(&self, hash: &str) -> Result<(BlobEntry, PathBuf), BlobError> {
+        validate_hash(hash)?;
+        let entry = {
+            let mut index = self.index.lock().unwrap();
+            let entry = index
+                .get_mut(hash)
+                .ok_or_else(|| BlobError::NotFound(hash.to_string()))?;
+            entry.last_accessed = Utc::now();
+            entry.clone()
+        };
+        self.touched.lock().unwrap().insert(entry.hash.clone());
+
+        let path = self.blob_path(hash);
+        if !path.is_file() {
+            return Err(BlobError::NotFound(hash.to_string()));
+        }
+        Ok((entry, path))
+    }
+
+    pub fn add_ref(&self, hash: &str, owner: &str) -> Result<BlobEntry, BlobError> {
+        validate_hash(hash)?;
+        let entry = {
+            let mut index = self.index.lock().unwrap();
+            let entry = index
+                .get_mut(hash)
+                .ok_or_else(|| BlobError::NotFound(hash.to_string()))?;
+            entry.refs.insert(owner.to_string());
+            entry.clone()
+        };
+        self.persist(&entry)?;
+        self.save()?;
+        Ok(entry)
+    }
+
+    pub fn release_ref(&self, hash: &str, owner: &str) -> Result<(), BlobError> {
+        validate_hash(hash)?;
+        let entry = {
+            let mut index = self.index.lock().unwrap();
+            let entry = index
+                .get_mut(hash)
+                .ok_or_else(|| BlobError::NotFound(hash.to_string()))?;
+            entry.refs.remove(owner);
+            entry.clone()
+        };
+        self.persist(&entry)?;
+        self.save()
+    }
+
+    pub fn release_owner(&self, owner: &str) -> Result<usize, BlobError> {
+        let released: Vec<BlobEntry> = {
+            let mut index = self.index.lock().unwrap();
+            index
+                .values_mut()
+                .filter_map(|entry| entry.refs.remove(owner).then(|| entry.clone()))
+                .collect()
+        };
+        for entry in &released {
+            self.persist(entry)?;
+        }
+        self.save()?;
+        Ok(released.len())
+    }
+
+    fn remove_entry(&self, index: &mut HashMap<String, BlobEntry>, hash: &str) -> Option<BlobEntry> {
+        let entry = index.remove(hash)?;
+        if let Err(e) = fs::remove_file(self.blob_path(hash)) {
+            log::warn!("Failed to remove blob {}: {}", hash, e);
+        }
+        self.store.delete(format!("blob_{}", hash));
+        Some(entry)
+    }
+
+    // Evicts least-recently-used unreferenced blobs until `incoming` more bytes fit the
+    // quota. Blobs a conversation still refers to are never evicted; when dropping every
+    // unreferenced blob would not be enough, nothing is evicted and the quota error returned.
+    fn make_room(&self, index: &mut HashMap<String, BlobEntry>, incoming: u64) -> Result<(usize, u64), BlobError> {
+        let mut total: u64 = index.values().map(|e| e.size).sum::<u64>() + incoming;
+        if total <= self.quota_bytes {
+            return Ok((0, 0));
+        }
+
+        let mut candidates: Vec<(DateTime<Utc>, String, u64)> = index
+            .values()
+            .filter(|e| e.refs.is_empty())
+            .map(|e| (e.last_accessed, e.hash.clone(), e.size))
+            .collect();
+        let reclaimable: u64 = candidates.iter().map(|(_, _, size)| size).sum();
+        if total - reclaimable > self.quota_bytes {
+            return Err(BlobError::QuotaExceeded(incoming));
+        }
+        candidates.sort();
+
+        let (mut evicted, mut freed) = (0, 0);
+        for (_, hash, _) in candidates {
+            if total <= self.quota_bytes {
+                break;
+            }
+            if let Some(entry) = self.remove_entry(index, &hash) {
+                total -= entry.size;
+                freed += entry.size;
+                evicted += 1;
+            }
+        }
+        Ok((evicted, freed))
+    }
+
+    pub fn collect_garbage(&self) -> Result<GcStats, BlobError> {
+        let mut stats = GcStats::default();
+        let cutoff = Utc::now() - Duration::minutes(ORPHAN_GRACE_MINUTES);
+
+        {
+            let mut index = self.index.lock().unwrap();
+            let orphans: Vec<String> = index
+                .values()
+                .filter(|e| e.refs.is_empty() && e.last_accessed < cutoff)
+                .map(|e| e.hash.clone())
+                .collect();
+            for hash in orphans {
+                if let Some(entry) = self.remove_entry(&mut index, &hash) {
+                    stats.orphans_removed += 1;
+                    stats.bytes_freed += entry.size;
+                }
+            }
+
+            match self.make_room(&mut index, 0) {
+                Ok((evicted, freed)) => {
+                    stats.evicted = evicted;
+                    stats.bytes_freed += freed;
+                }
+                Err(BlobError::QuotaExceeded(_)) => {
+                    log::warn!("Blob store is over quota with referenced blobs alone; nothing evicted");
+                }
+                Err(e) => return Err(e),
+            }
+
+            // Files without an index entry are leftovers from crashes or lost index writes
+            for shard in fs::read_dir(&self.root)?.flatten() {
+                let Ok(files) = fs::read_dir(shard.path()) else {
+                    continue;
+                };
+                for file in files.flatten() {
+                    let hash = format!(
+                        "{}{}",
+                        shard.file_name().to_string_lossy(),
+                        file.file_name().to_string_lossy()
+                    );
+                    if !index.contains_key(&hash) && remove_stray(&file.path()) {
+                        stats.stray_files_removed += 1;
+                    }
+                }
+            }
+        }
+
+        self.save()?;
+        Ok(stats)
+    }
+
+    pub fn stats(&self) -> BlobStats {
+        let index = self.index.lock().unwrap();
+        BlobStats {
+            blob_count: index.len(),
+            total_bytes: index.values().map(|e| e.size).sum(),
+            quota_bytes: self.quota_bytes,
+        }
+    }
+}
+
+fn remove_stray(path: &Path) -> bool {
+    match fs::remove_file(path) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("Failed to remove stray blob file {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+// An indexed blob keeps its refs and creation time; only a hash seen for the first time
+// gets a new entry
+fn upsert<'a>(
+    index: &'a mut HashMap<String, BlobEntry>,
+    hash: &str,
+    size: u64,
+    mime_type: &str,
+    now: DateTime<Utc>,
+) -> &'a mut BlobEntry {
+    let entry = index.entry(hash.to_string()).or_insert_with(|| BlobEntry {
+        hash: hash.to_string(),
+        size,
+        mime_type: mime_type.to_string(),
+        created_at: now,
+        last_accessed: now,
+        refs: HashSet::new(),
+    });
+    entry.last_accessed = now;
+    entry
+}
+
+fn validate_hash(hash: &str) -> Result<(), BlobError> {
+    if hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        Ok(())
+    } else {
+        Err(BlobError::InvalidHash(hash.to_string()))
+    }
+}
+
+pub fn blob_url(hash: &str) -> String {
+    // Windows and Android webviews only reach custom schemes through this http form
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost/{}", BLOB_SCHEME, hash)
+    } else {
+        format!("{}://localhost/{}", BLOB_SCHEME, hash)
+    }
+}
+
+pub fn handle_blob_protocol(ctx: UriSchemeContext<'_, tauri::Wry>, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let app = ctx.app_handle().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let hash = request.uri().path().trim_start_matches('/').to_string();
+        let response = match app.state::<BlobStore>().locate(&hash) {
+            // The URL is the content hash, so the ETag is too and the response never goes stale
+            Ok((entry, path)) => file_response(
+                &request,
+                &path,
+                &entry.mime_type,
+                &format!("\"{}\"", entry.hash),
+                "public, max-age=31536000, immutable",
+            )
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to serve blob {}: {}", hash, e);
+                status_response(StatusCode::INTERNAL_SERVER_ERROR)
+            }),
+            Err(BlobError::InvalidHash(_)) => status_response(StatusCode::BAD_REQUEST),
+            Err(e) => {
+                log::warn!("Failed to serve blob {}: {}", hash, e);
+                status_response(StatusCode::NOT_FOUND)
+            }
+        };
+        responder.respond(response);
+    });
+}
+
+// Tauri commands
+#[command]
+pub async fn put_blob(
+    data: String,
+    mime_type: String,
+    owner: Option<String>,
+    blob_store: State<'_, BlobStore>,
+) -> Result<BlobEntry, CommandError> {
+    let bytes = general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| BlobError::StorageError(e.to_string()))?;
+    blob_store.put(&bytes, &mime_type, owner.as_deref()).map_err(CommandError::from)
+}
+
+#[command]
+pub async fn add_blob_ref(
+    hash: String,
+    owner: String,
+    blob_store: State<'_, BlobStore>,
+) -> Result<BlobEntry, CommandError> {
+    blob_store.add_ref(&hash, &owner).map_err(CommandError::from)
+}
+
+#[command]
+pub async fn release_blob_ref(
+    hash: String,
+    owner: String,
+    blob_store: State<'_, BlobStore>,
+) -> Result<(), CommandError> {
+    blob_store.release_ref(&hash, &owner).map_err(CommandError::from)
+}
+
+#[command]
+pub async fn release_conversation_blobs(
+    conversation_id: String,
+    blob_store: State<'_, BlobStore>,
+) -> Result<usize, CommandError> {
+    blob_store.release_owner(&conversation_id).map_err(CommandError::from)
+}
+
+#[command]
+pub async fn collect_blob_garbage(
+    blob_store: State<'_, BlobStore>,
+) -> Result<GcStats, CommandError> {
+    blob_store.collect_garbage().map_err(CommandError::from)
+}
+
+#[command]
+pub async fn get_blob_stats(
+    blob_store: State<'_, BlobStore>,
+) -> Result<BlobStats, CommandError> {
+    Ok(blob_store.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn a_known_blob_keeps_its_refs_and_creation_time() {
+        let created_at = Utc::now() - Duration::days(3);
+        let mut index = HashMap::new();
+        upsert(&mut index, HASH, 4, "text/plain", created_at)
+            .refs
+            .insert("conversation-1".to_string());
+
+        // As when put finds the hash indexed but its file missing and writes it again
+        let now = Utc::now();
+        let entry = upsert(&mut index, HASH, 4, "text/plain", now);
+        assert_eq!(entry.refs, HashSet::from(["conversation-1".to_string()]));
+        assert_eq!(entry.created_at, created_at);
+        assert_eq!(entry.last_accessed, now);
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn an_unknown_blob_gets_a_new_entry() {
+        let mut index = HashMap::new();
+        let now = Utc::now();
+        let entry = upsert(&mut index, HASH, 4, "text/plain", now);
+        assert!(entry.refs.is_empty());
+        assert_eq!(entry.created_at, now);
+        assert_eq!(entry.size, 4);
+        assert_eq!(entry.mime_type, "text/plain");
+    }
+}
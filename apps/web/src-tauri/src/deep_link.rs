@@ -1,60 +1,181 @@
 use serde::{Deserialize, Serialize};
-use tauri::{command, AppHandle, Emitter, Manager};
-use tauri_plugin_deep_link;
-use tauri_plugin_opener;
-use url::Url;
-use std::collections::HashMap;
-use crate::auth::AuthError;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use tauri_plugin_deep_link::DeepLinkExt;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use uuid::Uuid;
+use crate::auth::{AuthError, AuthManager};
+use crate::deep_link_policy::{redact, DeepLinkPolicy, DeepLinkRoute};
+use crate::error::CommandError;
+use crate::external_links::{ExternalLinks, LinkPurpose};
+use crate::share::{ShareInbox, ShareOutcome};
+
+// Must match plugins.deep-link.desktop.schemes in tauri.conf.json
+pub const AUTH_SCHEMES: &[&str] = &["symlog", "symlog-auth"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SchemeHandlerState {
+    AlreadyRegistered,
+    Fixed,
+    RegisteredToOther { handler: String },
+    Failed { reason: String },
+    // Bundled installers own the association on Windows and macOS
+    ManagedByPlatform,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemeHandlerStatus {
+    pub scheme: String,
+    pub state: SchemeHandlerState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolRegistration {
+    pub platform: String,
+    pub desktop_entry: Option<PathBuf>,
+    pub desktop_entry_repaired: bool,
+    pub schemes: Vec<SchemeHandlerStatus>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeepLinkEvent {
+    pub id: String,
     pub url: String,
     pub parsed_params: HashMap<String, String>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub route: DeepLinkRoute,
+    // The route has a side effect the webview must not carry out without asking the user
+    pub requires_confirmation: bool,
+    // Set for `symlog://share/...` links once the signature has been checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share: Option<ShareOutcome>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthCallbackData {
+    pub link_id: String,
     pub code: Option<String>,
     pub state: Option<String>,
     pub error: Option<String>,
     pub error_description: Option<String>,
 }
 
-pub async fn setup_deep_linking(app: &AppHandle) -> Result<(), AuthError> {
-    // Listen for deep link events
-    let app_handle = app.clone();
-    tauri_plugin_deep_link::onOpenUrl(app, move |urls| {
-        for url in urls {
-            if let Err(e) = handle_deep_link_url(&app_handle, &url) {
-                log::error!("Failed to handle deep link: {}", e);
+// Oldest links are dropped beyond this; a burst this large is not user-driven
+const MAX_PENDING_LINKS: usize = 32;
+
+struct PendingLink {
+    event: DeepLinkEvent,
+    // Handed to the webview, either emitted or returned from deep_link_ready
+    in_flight: bool,
+}
+
+#[derive(Default)]
+struct InboxState {
+    ready: bool,
+    // Received but not yet acknowledged, in arrival order
+    pending: VecDeque<PendingLink>,
+}
+
+// Holds every received link until the webview acknowledges it (lib/deep-links.ts). Each link is handed to the
+// webview once: emitted on arrival if the webview is ready, otherwise returned from the
+// next ready signal. Links stay in flight until acked, and a reloaded page that lost them
+// collects them with drain_deep_links.
+#[derive(Default)]
+pub struct DeepLinkInbox {
+    state: Mutex<InboxState>,
+}
+
+impl DeepLinkInbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&self, app: &AppHandle, event: DeepLinkEvent) -> Result<(), AuthError> {
+        let ready = {
+            let mut state = self.state.lock().unwrap();
+            // The same link opened twice is two events; only a repeated push is dropped
+            if state.pending.iter().any(|pending| pending.event.id == event.id) {
+                return Ok(());
+            }
+            if state.pending.len() >= MAX_PENDING_LINKS {
+                if let Some(dropped) = state.pending.pop_front() {
+                    log::warn!("Deep link inbox full, dropping {}", dropped.event.id);
+                }
+            }
+            state.pending.push_back(PendingLink {
+                event: event.clone(),
+                in_flight: state.ready,
+            });
+            state.ready
+        };
+
+        if ready {
+            if let Err(e) = deliver(app, &event) {
+                // Left for the next ready signal
+                self.set_in_flight(&event.id, false);
+                return Err(e);
             }
         }
-    });
+        Ok(())
+    }
 
-    Ok(())
-}
+    fn set_in_flight(&self, id: &str, in_flight: bool) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(pending) = state.pending.iter_mut().find(|pending| pending.event.id == id) {
+            pending.in_flight = in_flight;
+        }
+    }
 
-fn handle_deep_link_url(app: &AppHandle, url: &str) -> Result<(), AuthError> {
-    log::info!("Received deep link: {}", url);
-    
-    let parsed_url = Url::parse(url).map_err(|e| AuthError::InvalidUrl(e.to_string()))?;
-    
-    // Extract query parameters
-    let mut params = HashMap::new();
-    for (key, value) in parsed_url.query_pairs() {
-        params.insert(key.to_string(), value.to_string());
+    // Launch links can be reported both by get_current and the open-url event
+    fn has_url(&self, url: &str) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .pending
+            .iter()
+            .any(|pending| pending.event.url == url)
     }
-    
-    let deep_link_event = DeepLinkEvent {
-        url: url.to_string(),
-        parsed_params: params.clone(),
-        timestamp: chrono::Utc::now(),
-    };
-    
-    // Check if this is an auth callback
-    if parsed_url.path().contains("/auth/callback") || params.contains_key("code") {
+
+    fn mark_ready(&self) -> Vec<DeepLinkEvent> {
+        let mut state = self.state.lock().unwrap();
+        state.ready = true;
+        state
+            .pending
+            .iter_mut()
+            .filter(|pending| !pending.in_flight)
+            .map(|pending| {
+                pending.in_flight = true;
+                pending.event.clone()
+            })
+            .collect()
+    }
+
+    fn ack(&self, id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let before = state.pending.len();
+        state.pending.retain(|pending| pending.event.id != id);
+        state.pending.len() != before
+    }
+
+    fn drain(&self) -> Vec<DeepLinkEvent> {
+        self.state
+            .lock()
+            .unwrap()
+            .pending
+            .drain(..)
+            .map(|pending| pending.event)
+            .collect()
+    }
+}
+
+fn deliver(app: &AppHandle, deep_link_event: &DeepLinkEvent) -> Result<(), AuthError> {
+    let params = &deep_link_event.parsed_params;
+
+    if deep_link_event.route == DeepLinkRoute::AuthCallback {
         let callback_data = AuthCallbackData {
+            link_id: deep_link_event.id.clone(),
             code: params.get("code").cloned(),
             state: params.get("state").cloned(),
             error: params.get("error").cloned(),
@@ -67,56 +188,158 @@ fn handle_deep_link_url(app: &AppHandle, url: &str) -> Result<(), AuthError> {
     }
     
     // Emit general deep link event
-    app.emit("deep_link", &deep_link_event)
+    app.emit("deep_link", deep_link_event)
         .map_err(|e| AuthError::DeepLinkError(e.to_string()))?;
     
     Ok(())
 }
 
-#[command]
-pub async fn open_auth_url(url: String) -> Result<(), AuthError> {
-    log::info!("Opening auth URL: {}", url);
-    
-    // Validate URL before opening
-    let parsed_url = Url::parse(&url).map_err(|e| AuthError::InvalidUrl(e.to_string()))?;
-    
-    // Security check: only allow HTTPS URLs and specific localhost ports for development
-    match parsed_url.scheme() {
-        "https" => {},
-        "http" => {
-            if let Some(host) = parsed_url.host_str() {
-                if host != "localhost" && host != "127.0.0.1" {
-                    return Err(AuthError::InvalidUrl("HTTP only allowed for localhost".to_string()));
-                }
-            } else {
-                return Err(AuthError::InvalidUrl("Invalid host".to_string()));
+pub async fn setup_deep_linking(app: &AppHandle) -> Result<(), AuthError> {
+    // Listen for deep link events
+    let app_handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            if let Err(e) = handle_deep_link_url(&app_handle, url.as_str()) {
+                log::error!("Failed to handle deep link: {}", e);
             }
-        },
-        _ => return Err(AuthError::InvalidUrl("Only HTTP(S) URLs allowed".to_string())),
+        }
+    });
+
+    // Links that launched the app arrive before any listener exists
+    let current = app
+        .deep_link()
+        .get_current()
+        .map_err(|e| AuthError::DeepLinkError(e.to_string()))?;
+    let inbox = app.state::<DeepLinkInbox>();
+    for url in current.unwrap_or_default() {
+        if !inbox.has_url(url.as_str()) {
+            handle_deep_link_url(app, url.as_str())?;
+        }
     }
+
+    Ok(())
+}
+
+// Any local app can fire symlog:// links at us, so nothing reaches the webview
+// without passing the route policy first
+fn handle_deep_link_url(app: &AppHandle, url: &str) -> Result<(), AuthError> {
+    let auth_manager = app.state::<AuthManager>();
+    let link = match app
+        .state::<DeepLinkPolicy>()
+        .check(url, |state| auth_manager.is_pending_state(state))
+    {
+        Ok(link) => link,
+        Err(reason) => {
+            log::warn!("Rejected deep link {}: {}", redact(url), reason);
+            return Ok(());
+        }
+    };
+    log::info!("Received deep link: {}", redact(url));
+
+    // A callback is only trusted without asking when it answers a sign-in started here
+    let requires_confirmation = link.requires_confirmation
+        && !(link.route == DeepLinkRoute::AuthCallback
+            && link
+                .params
+                .get("state")
+                .is_some_and(|state| auth_manager.take_pending_state(state)));
     
-    // Open URL in default browser
-    tauri_plugin_opener::open_url(&url, None::<&str>)
-        .map_err(|e| AuthError::DeepLinkError(format!("Failed to open URL: {}", e)))?;
+    let share = (link.route == DeepLinkRoute::Share)
+        .then(|| app.state::<ShareInbox>().receive(&link.url));
     
-    Ok(())
+    let deep_link_event = DeepLinkEvent {
+        id: Uuid::new_v4().to_string(),
+        url: url.to_string(),
+        parsed_params: link.params,
+        timestamp: chrono::Utc::now(),
+        route: link.route,
+        requires_confirmation,
+        share,
+    };
+    
+    app.state::<DeepLinkInbox>().push(app, deep_link_event)
 }
 
 #[command]
-pub async fn register_auth_protocol() -> Result<(), AuthError> {
-    // Register custom protocol for auth callbacks
-    // This is handled by the deep-link plugin configuration in tauri.conf.json
-    Ok(())
+pub async fn open_auth_url(
+    url: String,
+    app: AppHandle,
+    links: State<'_, ExternalLinks>,
+) -> Result<(), CommandError> {
+    // Only the configured sign-in origins; see ExternalLinks for the policy
+    links
+        .open(&app, &url, LinkPurpose::Auth)
+        .await
+        .map_err(CommandError::from)
+}
+
+// Writes or repairs the handler entry and MIME associations; an association held by
+// another app is only taken over when `force` is set
+#[command]
+pub async fn register_auth_protocol(
+    force: Option<bool>,
+    app: AppHandle,
+) -> Result<ProtocolRegistration, CommandError> {
+    #[cfg(target_os = "linux")]
+    {
+        let force = force.unwrap_or(false);
+        tauri::async_runtime::spawn_blocking(move || crate::xdg::register_schemes(&app, AUTH_SCHEMES, force))
+            .await
+            .map_err(|e| AuthError::DeepLinkError(e.to_string()))?
+            .map_err(CommandError::from)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (force, app);
+        Ok(ProtocolRegistration {
+            platform: std::env::consts::OS.to_string(),
+            desktop_entry: None,
+            desktop_entry_repaired: false,
+            schemes: AUTH_SCHEMES
+                .iter()
+                .map(|scheme| SchemeHandlerStatus {
+                    scheme: scheme.to_string(),
+                    state: SchemeHandlerState::ManagedByPlatform,
+                })
+                .collect(),
+        })
+    }
 }
 
 #[command] 
-pub async fn get_current_deep_link() -> Result<Option<String>, AuthError> {
+pub async fn get_current_deep_link(app: AppHandle) -> Result<Option<String>, CommandError> {
     // Get the current deep link that started the app
-    match tauri_plugin_deep_link::get_current().await {
-        Ok(urls) => Ok(urls.first().cloned()),
+    match app.deep_link().get_current() {
+        Ok(urls) => Ok(urls.and_then(|urls| urls.first().map(|url| url.to_string()))),
         Err(e) => {
             log::warn!("Failed to get current deep link: {}", e);
             Ok(None)
         }
     }
+}
+
+// Called by the webview once its deep-link listeners are attached; returns the links that
+// arrived before it was ready. Later links are emitted as they arrive.
+#[command]
+pub async fn deep_link_ready(
+    inbox: State<'_, DeepLinkInbox>,
+) -> Result<Vec<DeepLinkEvent>, CommandError> {
+    Ok(inbox.mark_ready())
+}
+
+#[command]
+pub async fn ack_deep_link(
+    id: String,
+    inbox: State<'_, DeepLinkInbox>,
+) -> Result<bool, CommandError> {
+    Ok(inbox.ack(&id))
+}
+
+// Hands every pending link to the caller, in flight or not, and considers them acknowledged
+#[command]
+pub async fn drain_deep_links(
+    inbox: State<'_, DeepLinkInbox>,
+) -> Result<Vec<DeepLinkEvent>, CommandError> {
+    Ok(inbox.drain())
 }
\ No newline at end of file
@@ -0,0 +1,319 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+// Each route has its own budget of links per window, so a flood of share links cannot
+// starve sign-in callbacks; links that match no route share one more budget
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const UNROUTED_RATE_LIMIT: usize = 10;
+// Anything longer is rejected before parsing
+const MAX_LINK_BYTES: usize = 96 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeepLinkRoute {
+    AuthCallback,
+    Share,
+}
+
+struct ParamRule {
+    name: &'static str,
+    max_len: usize,
+    valid: fn(&str) -> bool,
+}
+
+struct RouteRule {
+    route: DeepLinkRoute,
+    scheme: &'static str,
+    host: &'static str,
+    // Accepted paths; `None` means exactly one non-empty path segment
+    paths: Option<&'static [&'static str]>,
+    params: &'static [ParamRule],
+    // At least one of these must be present
+    required_any: &'static [&'static str],
+    max_len: usize,
+    // Links accepted per RATE_WINDOW
+    rate_limit: usize,
+    // Routes whose effect must wait for the user; the webview is told via the event
+    requires_confirmation: bool,
+}
+
+fn url_safe(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~'))
+}
+
+fn error_code(value: &str) -> bool {
+    value.bytes().all(|b| b.is_ascii_lowercase() || b == b'_')
+}
+
+fn printable(value: &str) -> bool {
+    !value.chars().any(char::is_control)
+}
+
+const AUTH_PARAMS: &[ParamRule] = &[
+    ParamRule { name: "code", max_len: 512, valid: url_safe },
+    ParamRule { name: "state", max_len: 128, valid: url_safe },
+    // PKCE verifiers are 43-128 unreserved characters (RFC 7636)
+    ParamRule { name: "verifier", max_len: 128, valid: url_safe },
+    ParamRule { name: "error", max_len: 64, valid: error_code },
+    ParamRule { name: "error_description", max_len: 512, valid: printable },
+];
+
+const ROUTES: &[RouteRule] = &[
+    // NEXT_PUBLIC_AUTH_REDIRECT_URL is symlog://auth; /callback is the documented form
+    RouteRule {
+        route: DeepLinkRoute::AuthCallback,
+        scheme: "symlog",
+        host: "auth",
+        paths: Some(&["", "/", "/callback"]),
+        params: AUTH_PARAMS,
+        required_any: &["code", "error"],
+        max_len: 4096,
+        rate_limit: 10,
+        // Any local app can hand us its own code (login CSRF); see handle_deep_link_url
+        requires_confirmation: true,
+    },
+    RouteRule {
+        route: DeepLinkRoute::AuthCallback,
+        scheme: "symlog-auth",
+        host: "callback",
+        paths: Some(&["", "/"]),
+        params: AUTH_PARAMS,
+        required_any: &["code", "error"],
+        max_len: 4096,
+        rate_limit: 10,
+        requires_confirmation: true,
+    },
+    // The import only happens through confirm_share_import
+    RouteRule {
+        route: DeepLinkRoute::Share,
+        scheme: "symlog",
+        host: "share",
+        paths: None,
+        params: &[],
+        required_any: &[],
+        max_len: MAX_LINK_BYTES,
+        rate_limit: 10,
+        requires_confirmation: true,
+    },
+];
+
+#[derive(Debug, Clone)]
+pub struct AcceptedLink {
+    pub url: Url,
+    pub route: DeepLinkRoute,
+    pub params: HashMap<String, String>,
+    pub requires_confirmation: bool,
+}
+
+fn check_route(rule: &RouteRule, raw: &str, url: &Url) -> Result<HashMap<String, String>, String> {
+    if raw.len() > rule.max_len {
+        return Err(format!("{} bytes exceeds the route limit of {}", raw.len(), rule.max_len));
+    }
+    if url.fragment().is_some() || !url.username().is_empty() || url.password().is_some() || url.port().is_some() {
+        return Err("link carries a fragment, credentials or port".to_string());
+    }
+
+    let path = url.path();
+    let path_ok = match rule.paths {
+        Some(paths) => paths.contains(&path),
+        None => path.len() > 1 && !path[1..].contains('/'),
+    };
+    if !path_ok {
+        return Err(format!("path {:?} is not allowed", path));
+    }
+
+    let mut params = HashMap::new();
+    for (name, value) in url.query_pairs() {
+        let rule = rule
+            .params
+            .iter()
+            .find(|param| param.name == name)
+            .ok_or_else(|| format!("unexpected parameter {:?}", name))?;
+        if value.is_empty() || value.len() > rule.max_len || !(rule.valid)(&value) {
+            return Err(format!("parameter {} is malformed", name));
+        }
+        if params.insert(name.to_string(), value.to_string()).is_some() {
+            return Err(format!("parameter {} is repeated", name));
+        }
+    }
+    if !rule.required_any.is_empty() && !rule.required_any.iter().any(|name| params.contains_key(*name)) {
+        return Err(format!("one of {:?} is required", rule.required_any));
+    }
+    Ok(params)
+}
+
+// Scheme, host and path only; queries carry auth codes and must never reach the log
+pub fn redact(raw: &str) -> String {
+    match Url::parse(raw) {
+        Ok(url) => {
+            let path: String = url.path().chars().take(48).collect();
+            format!("{}://{}{}", url.scheme(), url.host_str().unwrap_or(""), path)
+        }
+        Err(_) => "<unparseable>".to_string(),
+    }
+}
+
+#[derive(Default)]
+pub struct DeepLinkPolicy {
+    // Arrival times per route; `None` is for links that match no route
+    recent: Mutex<HashMap<Option<DeepLinkRoute>, VecDeque<Instant>>>,
+}
+
+impl DeepLinkPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn admit(&self, route: Option<DeepLinkRoute>, limit: usize) -> Result<(), String> {
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap();
+        let recent = recent.entry(route).or_default();
+        while recent.front().is_some_and(|at| now.duration_since(*at) > RATE_WINDOW) {
+            recent.pop_front();
+        }
+        if recent.len() >= limit {
+            return Err(format!("rate limit of {} links per {}s exceeded", limit, RATE_WINDOW.as_secs()));
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+
+    fn route(raw: &str) -> Result<(&'static RouteRule, Url), String> {
+        if raw.len() > MAX_LINK_BYTES {
+            return Err(format!("{} bytes exceeds the link limit", raw.len()));
+        }
+        let url = Url::parse(raw).map_err(|e| format!("not a URL: {}", e))?;
+        let host = url.host_str().unwrap_or("");
+        let rule = ROUTES
+            .iter()
+            .find(|rule| rule.scheme == url.scheme() && rule.host == host)
+            .ok_or_else(|| format!("no route for {}://{}", url.scheme(), host))?;
+        Ok((rule, url))
+    }
+
+    // `pending_state` tells whether a callback's state belongs to a sign-in started here;
+    // such a callback is expected and never throttled. It is not consumed here.
+    pub fn check(&self, raw: &str, pending_state: impl Fn(&str) -> bool) -> Result<AcceptedLink, String> {
+        let (rule, url) = match Self::route(raw) {
+            Ok(routed) => routed,
+            Err(reason) => {
+                self.admit(None, UNROUTED_RATE_LIMIT)?;
+                return Err(reason);
+            }
+        };
+
+        let params = check_route(rule, raw, &url);
+        let expected = rule.route == DeepLinkRoute::AuthCallback
+            && params
+                .as_ref()
+                .is_ok_and(|params| params.get("state").is_some_and(|state| pending_state(state.as_str())));
+        if !expected {
+            self.admit(Some(rule.route), rule.rate_limit)?;
+        }
+
+        Ok(AcceptedLink {
+            url,
+            route: rule.route,
+            params: params?,
+            requires_confirmation: rule.requires_confirmation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALLBACK: &str = "symlog://auth?code=abc123&verifier=v3rifier&state=s7ate";
+
+    fn unexpected(_: &str) -> bool {
+        false
+    }
+
+    #[test]
+    fn auth_callbacks_are_validated() {
+        let policy = DeepLinkPolicy::new();
+        let link = policy.check(CALLBACK, unexpected).unwrap();
+        assert_eq!(link.route, DeepLinkRoute::AuthCallback);
+        assert_eq!(link.params["code"], "abc123");
+        assert!(link.requires_confirmation);
+        assert!(policy.check("symlog-auth://callback/?error=access_denied", unexpected).is_ok());
+
+        for rejected in [
+            "symlog://auth?state=s7ate",
+            "symlog://auth?code=abc&extra=1",
+            "symlog://auth?code=abc&code=def",
+            "symlog://auth?code=a%20b",
+            "symlog://auth/elsewhere?code=abc",
+            "symlog://auth?code=abc#fragment",
+            "symlog://user@auth?code=abc",
+            "symlog://settings?code=abc",
+            "https://auth?code=abc",
+            "not a url",
+        ] {
+            assert!(policy.check(rejected, unexpected).is_err(), "{}", rejected);
+        }
+    }
+
+    #[test]
+    fn oversized_links_are_rejected() {
+        let policy = DeepLinkPolicy::new();
+        let code = "a".repeat(4096);
+        assert!(policy.check(&format!("symlog://auth?code={}", code), unexpected).is_err());
+        let payload = "a".repeat(MAX_LINK_BYTES);
+        assert!(policy.check(&format!("symlog://share/{}", payload), unexpected).is_err());
+    }
+
+    #[test]
+    fn share_links_need_one_path_segment() {
+        let policy = DeepLinkPolicy::new();
+        assert_eq!(policy.check("symlog://share/payload.signature", unexpected).unwrap().route, DeepLinkRoute::Share);
+        assert!(policy.check("symlog://share/", unexpected).is_err());
+        assert!(policy.check("symlog://share/a/b", unexpected).is_err());
+        assert!(policy.check("symlog://share/payload?x=1", unexpected).is_err());
+    }
+
+    #[test]
+    fn each_route_has_its_own_budget() {
+        let policy = DeepLinkPolicy::new();
+        for _ in 0..10 {
+            assert!(policy.check("symlog://share/payload.signature", unexpected).is_ok());
+        }
+        let refused = policy.check("symlog://share/payload.signature", unexpected).unwrap_err();
+        assert!(refused.contains("rate limit"), "{}", refused);
+
+        // A share flood leaves sign-in callbacks alone
+        assert!(policy.check(CALLBACK, unexpected).is_ok());
+    }
+
+    #[test]
+    fn rejected_links_still_count() {
+        let policy = DeepLinkPolicy::new();
+        for _ in 0..10 {
+            assert!(!policy.check("symlog://nowhere", unexpected).unwrap_err().contains("rate limit"));
+        }
+        assert!(policy.check("symlog://nowhere", unexpected).unwrap_err().contains("rate limit"));
+        for _ in 0..10 {
+            let _ = policy.check("symlog://auth?bogus=1", unexpected);
+        }
+        assert!(policy.check(CALLBACK, unexpected).unwrap_err().contains("rate limit"));
+    }
+
+    #[test]
+    fn callbacks_for_pending_sign_ins_are_never_throttled() {
+        let policy = DeepLinkPolicy::new();
+        for _ in 0..10 {
+            policy.check("symlog://auth?code=flood", unexpected).unwrap();
+        }
+        assert!(policy.check(CALLBACK, unexpected).is_err());
+        let pending = |state: &str| state == "s7ate";
+        assert!(policy.check(CALLBACK, pending).is_ok());
+        // The exemption is for the state, not for any callback
+        assert!(policy.check("symlog://auth?code=abc&state=other", pending).is_err());
+    }
+}
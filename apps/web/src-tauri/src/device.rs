@@ -0,0 +1,121 @@
+use std::fs;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, State};
+use tauri_plugin_store::{Store, StoreExt};
+use sha2::{Digest, Sha256};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::auth::{AuthError, AuthManager, DeviceInfo, StoreNamespace, AUTH_STORE};
+use crate::error::CommandError;
+
+const MACHINE_ID_PATHS: &[&str] = &["/etc/machine-id", "/var/lib/dbus/machine-id"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    pub device_id: String,
+    pub device_name: String,
+    pub platform: String,
+    pub os_version: String,
+    pub arch: String,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct DeviceManager {
+    identity: DeviceIdentity,
+}
+
+impl DeviceManager {
+    pub fn new(app: &AppHandle, auth_manager: &AuthManager) -> Result<Self, AuthError> {
+        let store = app
+            .store(AUTH_STORE)
+            .map_err(|e| AuthError::StorageError(e.to_string()))?;
+
+        let device_id = derive_device_id(&machine_id(&store)?, auth_manager.install_salt());
+        let previous = store
+            .get(StoreNamespace::Device.key("identity"))
+            .and_then(|value| serde_json::from_value::<DeviceIdentity>(value).ok());
+
+        // Hostname and OS version may change between runs; the id and first-seen date may not
+        let info = os_info::get();
+        let identity = DeviceIdentity {
+            created_at: previous
+                .filter(|p| p.device_id == device_id)
+                .map(|p| p.created_at)
+                .unwrap_or_else(Utc::now),
+            device_id,
+            device_name: hostname::get()
+                .map(|h| h.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "Desktop".to_string()),
+            platform: std::env::consts::OS.to_string(),
+            os_version: format!("{} {}", info.os_type(), info.version()),
+            arch: std::env::consts::ARCH.to_string(),
+            app_version: app.package_info().version.to_string(),
+        };
+
+        let value = serde_json::to_value(&identity).map_err(|e| AuthError::StorageError(e.to_string()))?;
+        store.set(StoreNamespace::Device.key("identity"), value);
+        store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
+
+        Ok(Self { identity })
+    }
+
+    pub fn identity(&self) -> &DeviceIdentity {
+        &self.identity
+    }
+
+    pub fn device_info(&self) -> DeviceInfo {
+        DeviceInfo {
+            device_id: self.identity.device_id.clone(),
+            device_name: self.identity.device_name.clone(),
+            platform: self.identity.platform.clone(),
+            user_agent: Some(format!(
+                "SYMLog/{} ({}; {})",
+                self.identity.app_version, self.identity.os_version, self.identity.arch
+            )),
+        }
+    }
+}
+
+// Falls back to a random per-install id where the OS exposes no machine id
+fn machine_id(store: &Arc<Store<tauri::Wry>>) -> Result<String, AuthError> {
+    for path in MACHINE_ID_PATHS {
+        if let Ok(id) = fs::read_to_string(path) {
+            let id = id.trim();
+            if !id.is_empty() {
+                return Ok(id.to_string());
+            }
+        }
+    }
+
+    let key = StoreNamespace::Device.key("install_id");
+    if let Some(id) = store.get(&key).and_then(|v| v.as_str().map(str::to_string)) {
+        return Ok(id);
+    }
+    let id = Uuid::new_v4().to_string();
+    store.set(key, serde_json::Value::String(id.clone()));
+    store.save().map_err(|e| AuthError::StorageError(e.to_string()))?;
+    Ok(id)
+}
+
+// The raw machine id is never sent anywhere; salting keeps ids unlinkable across installs
+fn derive_device_id(machine_id: &str, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"symlog-device-id:");
+    hasher.update(salt.as_bytes());
+    hasher.update(b":");
+    hasher.update(machine_id.as_bytes());
+    hasher.finalize()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Tauri commands
+#[command]
+pub async fn get_device_identity(
+    device_manager: State<'_, DeviceManager>,
+) -> Result<DeviceIdentity, CommandError> {
+    Ok(device_manager.identity().clone())
+}
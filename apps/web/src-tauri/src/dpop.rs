@@ -0,0 +1,162 @@
+use serde_json::json;
+use tauri::{command, AppHandle, State};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use chrono::Utc;
+use url::Url;
+use uuid::Uuid;
+use zeroize::{Zeroize, Zeroizing};
+use crate::auth::{AuthError, AuthManager};
+use crate::device::DeviceManager;
+use crate::error::CommandError;
+use crate::keychain::KeyStorage;
+
+const DEVICE_KEY_SECRET: &str = "device_signing_key_v2";
+// Sealed with the device id as passphrase, which is not secret; migrated on first load
+const LEGACY_DEVICE_KEY_SECRET: &str = "device_signing_key";
+// Keyring account holding the random passphrase the device key is sealed with
+const DEVICE_KEY_PASSPHRASE_ACCOUNT: &str = "device-key-passphrase";
+
+// Per-install Ed25519 key used to sign RFC 9449 DPoP proofs
+pub struct DeviceKey {
+    signing_key: SigningKey,
+    public_x: String,
+    thumbprint: String,
+}
+
+impl DeviceKey {
+    pub async fn load_or_create(
+        app: &AppHandle,
+        auth_manager: &AuthManager,
+        device_manager: &DeviceManager,
+    ) -> Result<Self, AuthError> {
+        let storage = KeyStorage::open(app, DEVICE_KEY_PASSPHRASE_ACCOUNT)?;
+        let passphrase = match storage.load()? {
+            Some(passphrase) => passphrase,
+            // The sealed key is only readable with the passphrase it was sealed with
+            None if auth_manager.has_secret(DEVICE_KEY_SECRET) => {
+                return Err(AuthError::KeyUnavailable(
+                    "the device key is sealed but its passphrase is missing from key storage".to_string(),
+                ))
+            }
+            None => Self::new_passphrase(&storage)?,
+        };
+
+        if let Some(bytes) = auth_manager.retrieve_secret(DEVICE_KEY_SECRET, &passphrase).await? {
+            return Ok(Self::from_signing_key(Self::signing_key(bytes)?));
+        }
+
+        // The legacy copy is only dropped once the re-sealed one is stored
+        let legacy_passphrase = &device_manager.identity().device_id;
+        let (signing_key, migrated) = match auth_manager.retrieve_secret(LEGACY_DEVICE_KEY_SECRET, legacy_passphrase).await? {
+            Some(bytes) => (Self::signing_key(bytes)?, true),
+            None => {
+                let mut seed = [0u8; 32];
+                OsRng.fill_bytes(&mut seed);
+                let key = SigningKey::from_bytes(&seed);
+                seed.zeroize();
+                (key, false)
+            }
+        };
+        auth_manager.store_secret(DEVICE_KEY_SECRET, &signing_key.to_bytes(), &passphrase).await?;
+        if migrated {
+            auth_manager.clear_secret(LEGACY_DEVICE_KEY_SECRET)?;
+            log::info!("Re-sealed device signing key under its keyring passphrase");
+        } else {
+            log::info!("Generated new device signing key");
+        }
+        Ok(Self::from_signing_key(signing_key))
+    }
+
+    // Random and kept in the keyring, so auth.json alone never opens the device key
+    fn new_passphrase(storage: &KeyStorage) -> Result<Zeroizing<String>, AuthError> {
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(&mut *bytes);
+        let passphrase = Zeroizing::new(general_purpose::URL_SAFE_NO_PAD.encode(&*bytes));
+        storage.save(&passphrase)?;
+        Ok(passphrase)
+    }
+
+    fn signing_key(mut bytes: Vec<u8>) -> Result<SigningKey, AuthError> {
+        let seed: Result<[u8; 32], _> = bytes.as_slice().try_into();
+        bytes.zeroize();
+        let mut seed = seed.map_err(|_| AuthError::CryptoError("Stored device key is malformed".to_string()))?;
+        let key = SigningKey::from_bytes(&seed);
+        seed.zeroize();
+        Ok(key)
+    }
+
+    pub(crate) fn from_signing_key(signing_key: SigningKey) -> Self {
+        let public_x = general_purpose::URL_SAFE_NO_PAD.encode(signing_key.verifying_key().as_bytes());
+        // RFC 7638: members in lexicographic order, no whitespace
+        let canonical_jwk = format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, public_x);
+        let thumbprint = general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(canonical_jwk.as_bytes()));
+
+        Self {
+            signing_key,
+            public_x,
+            thumbprint,
+        }
+    }
+
+    pub fn thumbprint(&self) -> &str {
+        &self.thumbprint
+    }
+
+    pub fn jwk(&self) -> serde_json::Value {
+        json!({ "kty": "OKP", "crv": "Ed25519", "x": self.public_x })
+    }
+
+    pub fn proof(
+        &self,
+        method: &str,
+        url: &str,
+        access_token: Option<&str>,
+        nonce: Option<&str>,
+    ) -> Result<String, AuthError> {
+        // `htu` is the target URI without query and fragment
+        let mut htu = Url::parse(url).map_err(|e| AuthError::InvalidUrl(e.to_string()))?;
+        htu.set_query(None);
+        htu.set_fragment(None);
+
+        let header = json!({
+            "typ": "dpop+jwt",
+            "alg": "EdDSA",
+            "jwk": self.jwk(),
+        });
+        let mut claims = json!({
+            "jti": Uuid::new_v4().to_string(),
+            "htm": method.to_uppercase(),
+            "htu": htu.as_str(),
+            "iat": Utc::now().timestamp(),
+        });
+        if let Some(access_token) = access_token {
+            claims["ath"] = json!(general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes())));
+        }
+        if let Some(nonce) = nonce {
+            claims["nonce"] = json!(nonce);
+        }
+
+        let signing_input = format!(
+            "{}.{}",
+            general_purpose::URL_SAFE_NO_PAD.encode(header.to_string()),
+            general_purpose::URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = self.signing_key.sign(signing_input.as_bytes());
+        Ok(format!(
+            "{}.{}",
+            signing_input,
+            general_purpose::URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        ))
+    }
+}
+
+// Tauri commands
+#[command]
+pub async fn get_device_key_thumbprint(
+    device_key: State<'_, DeviceKey>,
+) -> Result<String, CommandError> {
+    Ok(device_key.thumbprint().to_string())
+}
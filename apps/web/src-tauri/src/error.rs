@@ -0,0 +1,439 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::command;
+use crate::auth::AuthError;
+use crate::blob_store::BlobError;
+use crate::external_links::ExternalLinkError;
+use crate::files::FileError;
+use crate::ipc_guard::GuardError;
+use crate::share::ShareError;
+
+// Stable, machine-readable codes returned to the webview. Codes are never renamed or
+// reused; the mirror in src/types/command-errors.ts must list the same set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    #[serde(rename = "auth.invalid_code")]
+    AuthInvalidCode,
+    #[serde(rename = "auth.expired_code")]
+    AuthExpiredCode,
+    #[serde(rename = "auth.pkce_failed")]
+    AuthPkceFailed,
+    #[serde(rename = "auth.storage")]
+    AuthStorage,
+    #[serde(rename = "auth.crypto")]
+    AuthCrypto,
+    #[serde(rename = "auth.invalid_url")]
+    AuthInvalidUrl,
+    #[serde(rename = "auth.deep_link")]
+    AuthDeepLink,
+    #[serde(rename = "auth.invalid_token")]
+    AuthInvalidToken,
+    #[serde(rename = "auth.network")]
+    AuthNetwork,
+    #[serde(rename = "auth.confirmation_required")]
+    AuthConfirmationRequired,
+    #[serde(rename = "auth.unknown_session")]
+    AuthUnknownSession,
+    #[serde(rename = "auth.current_session")]
+    AuthCurrentSession,
+    #[serde(rename = "file.unknown_handle")]
+    FileUnknownHandle,
+    #[serde(rename = "file.access_denied")]
+    FileAccessDenied,
+    #[serde(rename = "file.chunk_too_large")]
+    FileChunkTooLarge,
+    #[serde(rename = "file.out_of_order_write")]
+    FileOutOfOrderWrite,
+    #[serde(rename = "file.invalid_path")]
+    FileInvalidPath,
+    #[serde(rename = "file.policy_violation")]
+    FilePolicyViolation,
+    #[serde(rename = "file.invalid_artifact")]
+    FileInvalidArtifact,
+    #[serde(rename = "file.encoding")]
+    FileEncoding,
+    #[serde(rename = "file.io")]
+    FileIo,
+    #[serde(rename = "blob.not_found")]
+    BlobNotFound,
+    #[serde(rename = "blob.invalid_hash")]
+    BlobInvalidHash,
+    #[serde(rename = "blob.quota_exceeded")]
+    BlobQuotaExceeded,
+    #[serde(rename = "blob.storage")]
+    BlobStorage,
+    #[serde(rename = "share.invalid_link")]
+    ShareInvalidLink,
+    #[serde(rename = "share.untrusted_key")]
+    ShareUntrustedKey,
+    #[serde(rename = "share.bad_signature")]
+    ShareBadSignature,
+    #[serde(rename = "share.expired")]
+    ShareExpired,
+    #[serde(rename = "share.unknown")]
+    ShareUnknown,
+    #[serde(rename = "share.too_large")]
+    ShareTooLarge,
+    #[serde(rename = "share.network")]
+    ShareNetwork,
+    #[serde(rename = "share.import_failed")]
+    ShareImportFailed,
+    #[serde(rename = "link.invalid_url")]
+    LinkInvalidUrl,
+    #[serde(rename = "link.blocked")]
+    LinkBlocked,
+    #[serde(rename = "link.declined")]
+    LinkDeclined,
+    #[serde(rename = "link.open_failed")]
+    LinkOpenFailed,
+    #[serde(rename = "ipc.denied")]
+    IpcDenied,
+    #[serde(rename = "ipc.vault_locked")]
+    IpcVaultLocked,
+    #[serde(rename = "ipc.session_required")]
+    IpcSessionRequired,
+    #[serde(rename = "ipc.missing_scope")]
+    IpcMissingScope,
+    #[serde(rename = "internal")]
+    Internal,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::AuthInvalidCode,
+        ErrorCode::AuthExpiredCode,
+        ErrorCode::AuthPkceFailed,
+        ErrorCode::AuthStorage,
+        ErrorCode::AuthCrypto,
+        ErrorCode::AuthInvalidUrl,
+        ErrorCode::AuthDeepLink,
+        ErrorCode::AuthInvalidToken,
+        ErrorCode::AuthNetwork,
+        ErrorCode::AuthConfirmationRequired,
+        ErrorCode::AuthUnknownSession,
+        ErrorCode::AuthCurrentSession,
+        ErrorCode::FileUnknownHandle,
+        ErrorCode::FileAccessDenied,
+        ErrorCode::FileChunkTooLarge,
+        ErrorCode::FileOutOfOrderWrite,
+        ErrorCode::FileInvalidPath,
+        ErrorCode::FilePolicyViolation,
+        ErrorCode::FileInvalidArtifact,
+        ErrorCode::FileEncoding,
+        ErrorCode::FileIo,
+        ErrorCode::BlobNotFound,
+        ErrorCode::BlobInvalidHash,
+        ErrorCode::BlobQuotaExceeded,
+        ErrorCode::BlobStorage,
+        ErrorCode::ShareInvalidLink,
+        ErrorCode::ShareUntrustedKey,
+        ErrorCode::ShareBadSignature,
+        ErrorCode::ShareExpired,
+        ErrorCode::ShareUnknown,
+        ErrorCode::ShareTooLarge,
+        ErrorCode::ShareNetwork,
+        ErrorCode::ShareImportFailed,
+        ErrorCode::LinkInvalidUrl,
+        ErrorCode::LinkBlocked,
+        ErrorCode::LinkDeclined,
+        ErrorCode::LinkOpenFailed,
+        ErrorCode::IpcDenied,
+        ErrorCode::IpcVaultLocked,
+        ErrorCode::IpcSessionRequired,
+        ErrorCode::IpcMissingScope,
+        ErrorCode::Internal,
+    ];
+
+    // Whether repeating the same call unchanged may succeed
+    pub fn retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::AuthStorage
+                | ErrorCode::AuthDeepLink
+                | ErrorCode::AuthNetwork
+                | ErrorCode::FileIo
+                | ErrorCode::BlobStorage
+                | ErrorCode::ShareNetwork
+                | ErrorCode::LinkOpenFailed
+                | ErrorCode::Internal
+        )
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorCode::AuthInvalidCode => "The authentication code or state is missing or invalid",
+            ErrorCode::AuthExpiredCode => "The authentication code or session has expired",
+            ErrorCode::AuthPkceFailed => "The PKCE verifier did not match the challenge",
+            ErrorCode::AuthStorage => "The auth store could not be read or written",
+            ErrorCode::AuthCrypto => "Encrypting or decrypting auth data failed",
+            ErrorCode::AuthInvalidUrl => "A URL was malformed or not permitted",
+            ErrorCode::AuthDeepLink => "Handling or opening a deep link failed",
+            ErrorCode::AuthInvalidToken => "The access token failed signature or claim validation",
+            ErrorCode::AuthNetwork => "The auth service could not be reached",
+            ErrorCode::AuthConfirmationRequired => "A destructive action was not confirmed",
+            ErrorCode::AuthUnknownSession => "The session does not exist or belongs to another account",
+            ErrorCode::AuthCurrentSession => "The current device's session must be ended by signing out",
+            ErrorCode::FileUnknownHandle => "The file handle does not exist or was closed",
+            ErrorCode::FileAccessDenied => "The file handle does not permit this operation",
+            ErrorCode::FileChunkTooLarge => "A file chunk exceeded the maximum chunk size",
+            ErrorCode::FileOutOfOrderWrite => "A file chunk was written at an unexpected offset",
+            ErrorCode::FileInvalidPath => "A file path was malformed or outside the allowed scope",
+            ErrorCode::FilePolicyViolation => "A file was rejected by the attachment policy",
+            ErrorCode::FileInvalidArtifact => "An artifact could not be converted to its file format",
+            ErrorCode::FileEncoding => "File content could not be encoded or decoded",
+            ErrorCode::FileIo => "A file system operation failed",
+            ErrorCode::BlobNotFound => "The blob does not exist in the local store",
+            ErrorCode::BlobInvalidHash => "The blob hash is not a SHA-256 hex digest",
+            ErrorCode::BlobQuotaExceeded => "The blob does not fit in the local store quota without evicting referenced blobs",
+            ErrorCode::BlobStorage => "The blob store could not be read or written",
+            ErrorCode::ShareInvalidLink => "A share link was malformed",
+            ErrorCode::ShareUntrustedKey => "A share link was signed by a key that is not trusted",
+            ErrorCode::ShareBadSignature => "A share link signature did not verify",
+            ErrorCode::ShareExpired => "A share link has expired",
+            ErrorCode::ShareUnknown => "The share does not exist, expired or was already handled",
+            ErrorCode::ShareTooLarge => "The shared conversation exceeds the size limit",
+            ErrorCode::ShareNetwork => "The shared conversation could not be fetched or verified",
+            ErrorCode::ShareImportFailed => "The shared conversation could not be stored",
+            ErrorCode::LinkInvalidUrl => "An external link was not a valid http(s) URL",
+            ErrorCode::LinkBlocked => "An external link is not allowed by the link policy",
+            ErrorCode::LinkDeclined => "The user declined to open an external link",
+            ErrorCode::LinkOpenFailed => "The system browser could not be launched",
+            ErrorCode::IpcDenied => "The calling window or origin may not invoke this command",
+            ErrorCode::IpcVaultLocked => "The vault is locked and must be unlocked first",
+            ErrorCode::IpcSessionRequired => "The command requires a verified, unexpired session",
+            ErrorCode::IpcMissingScope => "The active session lacks a scope the command requires",
+            ErrorCode::Internal => "An unexpected internal error occurred",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub retryable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            retryable: code.retryable(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<AuthError> for CommandError {
+    fn from(e: AuthError) -> Self {
+        let message = e.to_string();
+        match e {
+            AuthError::InvalidCode => CommandError::new(ErrorCode::AuthInvalidCode, message),
+            AuthError::ExpiredCode => CommandError::new(ErrorCode::AuthExpiredCode, message),
+            AuthError::PKCEFailed => CommandError::new(ErrorCode::AuthPkceFailed, message),
+            AuthError::StorageError(reason) => {
+                CommandError::new(ErrorCode::AuthStorage, message).with_details(json!({ "reason": reason }))
+            }
+            AuthError::CryptoError(reason) => {
+                CommandError::new(ErrorCode::AuthCrypto, message).with_details(json!({ "reason": reason }))
+            }
+            AuthError::KeyUnavailable(reason) => {
+                CommandError::new(ErrorCode::AuthStorage, message).with_details(json!({ "reason": reason }))
+            }
+            AuthError::InvalidUrl(reason) => {
+                CommandError::new(ErrorCode::AuthInvalidUrl, message).with_details(json!({ "reason": reason }))
+            }
+            AuthError::DeepLinkError(reason) => {
+                CommandError::new(ErrorCode::AuthDeepLink, message).with_details(json!({ "reason": reason }))
+            }
+            AuthError::InvalidToken(reason) => {
+                CommandError::new(ErrorCode::AuthInvalidToken, message).with_details(json!({ "reason": reason }))
+            }
+            AuthError::NetworkError(reason) => {
+                CommandError::new(ErrorCode::AuthNetwork, message).with_details(json!({ "reason": reason }))
+            }
+            AuthError::ConfirmationRequired(reason) => CommandError::new(ErrorCode::AuthConfirmationRequired, message)
+                .with_details(json!({ "reason": reason })),
+            AuthError::UnknownSession(session_id) => CommandError::new(ErrorCode::AuthUnknownSession, message)
+                .with_details(json!({ "sessionId": session_id })),
+            AuthError::CurrentSession => CommandError::new(ErrorCode::AuthCurrentSession, message),
+        }
+    }
+}
+
+impl From<FileError> for CommandError {
+    fn from(e: FileError) -> Self {
+        let message = e.to_string();
+        match e {
+            FileError::UnknownHandle(handle_id) => CommandError::new(ErrorCode::FileUnknownHandle, message)
+                .with_details(json!({ "handleId": handle_id })),
+            FileError::AccessDenied(reason) => {
+                CommandError::new(ErrorCode::FileAccessDenied, message).with_details(json!({ "reason": reason }))
+            }
+            FileError::ChunkTooLarge(bytes) => CommandError::new(ErrorCode::FileChunkTooLarge, message)
+                .with_details(json!({ "bytes": bytes, "maxBytes": crate::files::MAX_CHUNK_SIZE })),
+            FileError::OutOfOrderWrite(offset) => {
+                CommandError::new(ErrorCode::FileOutOfOrderWrite, message).with_details(json!({ "offset": offset }))
+            }
+            FileError::InvalidPath(reason) => {
+                CommandError::new(ErrorCode::FileInvalidPath, message).with_details(json!({ "reason": reason }))
+            }
+            FileError::PolicyViolation(reason) => {
+                CommandError::new(ErrorCode::FilePolicyViolation, message).with_details(json!({ "reason": reason }))
+            }
+            FileError::InvalidArtifact(reason) => {
+                CommandError::new(ErrorCode::FileInvalidArtifact, message).with_details(json!({ "reason": reason }))
+            }
+            FileError::EncodingError(reason) => {
+                CommandError::new(ErrorCode::FileEncoding, message).with_details(json!({ "reason": reason }))
+            }
+            FileError::IoError(reason) => {
+                CommandError::new(ErrorCode::FileIo, message).with_details(json!({ "reason": reason }))
+            }
+        }
+    }
+}
+
+impl From<BlobError> for CommandError {
+    fn from(e: BlobError) -> Self {
+        let message = e.to_string();
+        match e {
+            BlobError::NotFound(hash) => {
+                CommandError::new(ErrorCode::BlobNotFound, message).with_details(json!({ "hash": hash }))
+            }
+            BlobError::InvalidHash(hash) => {
+                CommandError::new(ErrorCode::BlobInvalidHash, message).with_details(json!({ "hash": hash }))
+            }
+            BlobError::QuotaExceeded(bytes) => {
+                CommandError::new(ErrorCode::BlobQuotaExceeded, message).with_details(json!({ "bytes": bytes }))
+            }
+            BlobError::StorageError(reason) => {
+                CommandError::new(ErrorCode::BlobStorage, message).with_details(json!({ "reason": reason }))
+            }
+        }
+    }
+}
+
+impl From<ShareError> for CommandError {
+    fn from(e: ShareError) -> Self {
+        let message = e.to_string();
+        match e {
+            ShareError::InvalidLink(reason) => {
+                CommandError::new(ErrorCode::ShareInvalidLink, message).with_details(json!({ "reason": reason }))
+            }
+            ShareError::UntrustedKey(kid) => {
+                CommandError::new(ErrorCode::ShareUntrustedKey, message).with_details(json!({ "keyId": kid }))
+            }
+            ShareError::BadSignature => CommandError::new(ErrorCode::ShareBadSignature, message),
+            ShareError::Expired => CommandError::new(ErrorCode::ShareExpired, message),
+            ShareError::UnknownShare(share_id) => {
+                CommandError::new(ErrorCode::ShareUnknown, message).with_details(json!({ "shareId": share_id }))
+            }
+            ShareError::TooLarge(bytes) => {
+                CommandError::new(ErrorCode::ShareTooLarge, message).with_details(json!({ "bytes": bytes }))
+            }
+            ShareError::NetworkError(reason) => {
+                CommandError::new(ErrorCode::ShareNetwork, message).with_details(json!({ "reason": reason }))
+            }
+            ShareError::ImportFailed(reason) => {
+                CommandError::new(ErrorCode::ShareImportFailed, message).with_details(json!({ "reason": reason }))
+            }
+        }
+    }
+}
+
+impl From<ExternalLinkError> for CommandError {
+    fn from(e: ExternalLinkError) -> Self {
+        let message = e.to_string();
+        match e {
+            ExternalLinkError::InvalidUrl(reason) => {
+                CommandError::new(ErrorCode::LinkInvalidUrl, message).with_details(json!({ "reason": reason }))
+            }
+            ExternalLinkError::Blocked(reason) => {
+                CommandError::new(ErrorCode::LinkBlocked, message).with_details(json!({ "reason": reason }))
+            }
+            ExternalLinkError::Declined(host) => {
+                CommandError::new(ErrorCode::LinkDeclined, message).with_details(json!({ "host": host }))
+            }
+            ExternalLinkError::OpenFailed(reason) => {
+                CommandError::new(ErrorCode::LinkOpenFailed, message).with_details(json!({ "reason": reason }))
+            }
+        }
+    }
+}
+
+impl From<GuardError> for CommandError {
+    fn from(e: GuardError) -> Self {
+        let message = e.to_string();
+        match e {
+            GuardError::WrongWindow { command, label } => CommandError::new(ErrorCode::IpcDenied, message)
+                .with_details(json!({ "command": command, "window": label })),
+            GuardError::WrongOrigin { command, origin } => CommandError::new(ErrorCode::IpcDenied, message)
+                .with_details(json!({ "command": command, "origin": origin })),
+            GuardError::VaultLocked(command) => {
+                CommandError::new(ErrorCode::IpcVaultLocked, message).with_details(json!({ "command": command }))
+            }
+            GuardError::SessionRequired(command) => {
+                CommandError::new(ErrorCode::IpcSessionRequired, message).with_details(json!({ "command": command }))
+            }
+            GuardError::MissingScopes { command, missing } => CommandError::new(ErrorCode::IpcMissingScope, message)
+                .with_details(json!({ "command": command, "missingScopes": missing })),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorCatalogueEntry {
+    pub code: ErrorCode,
+    pub retryable: bool,
+    pub description: String,
+}
+
+// Tauri commands
+#[command]
+pub async fn get_error_catalogue() -> Result<Vec<ErrorCatalogueEntry>, CommandError> {
+    Ok(ErrorCode::ALL
+        .iter()
+        .map(|code| ErrorCatalogueEntry {
+            code: *code,
+            retryable: code.retryable(),
+            description: code.description().to_string(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TS_ERRORS: &str = include_str!("../../src/types/command-errors.ts");
+
+    #[test]
+    fn codes_match_command_errors_ts() {
+        let start = TS_ERRORS.find("type CommandErrorCode =").expect("CommandErrorCode not found");
+        let body = &TS_ERRORS[start..start + TS_ERRORS[start..].find(';').unwrap()];
+        let ts_codes: Vec<&str> = body.split('\'').skip(1).step_by(2).collect();
+
+        let rust_codes: Vec<String> = ErrorCode::ALL
+            .iter()
+            .map(|code| serde_json::to_value(code).unwrap().as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(ts_codes, rust_codes);
+    }
+}
@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use thiserror::Error;
+use tokio::sync::oneshot;
+use url::{Host, Url};
+use crate::error::CommandError;
+
+// Comma-separated origins, e.g. "https://auth.example.com,https://api.example.com"
+const AUTH_ORIGINS_VAR: &str = "SYMLOG_AUTH_ORIGINS";
+// Comma-separated domains; subdomains of a listed domain are trusted too
+const TRUSTED_DOMAINS_VAR: &str = "SYMLOG_TRUSTED_LINK_DOMAINS";
+
+const DEFAULT_AUTH_ORIGINS: &[&str] = &["https://auth-web-two.vercel.app", "https://symlog-api.vercel.app"];
+// The project lives at github.com/symlog, but trust is per domain
+const DEFAULT_TRUSTED_DOMAINS: &[&str] = &[
+    "auth-web-two.vercel.app",
+    "symlog-api.vercel.app",
+    "github.com",
+    "crossmint.com",
+];
+// Local auth servers are only reachable from development builds
+const DEV_AUTH_ORIGINS: &[&str] = &["http://localhost:3000", "http://127.0.0.1:3000"];
+
+#[derive(Error, Debug)]
+pub enum ExternalLinkError {
+    #[error("Invalid link: {0}")]
+    InvalidUrl(String),
+    #[error("Link blocked: {0}")]
+    Blocked(String),
+    #[error("Opening {0} was declined")]
+    Declined(String),
+    #[error("Failed to open link: {0}")]
+    OpenFailed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkPurpose {
+    // Sign-in pages; only allowlisted origins, never prompted
+    Auth,
+    // Anything else the user clicked
+    Link,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkVerdict {
+    Open,
+    Prompt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkAssessment {
+    pub url: String,
+    pub verdict: LinkVerdict,
+    // Unicode form of the host when it differs from the ASCII one the browser will load
+    pub display_host: String,
+    pub punycode: bool,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalLinkConfig {
+    pub auth_origins: Vec<String>,
+    pub trusted_domains: Vec<String>,
+}
+
+fn env_list(var: &str, defaults: &[&str]) -> Vec<String> {
+    match std::env::var(var) {
+        Ok(value) if !value.trim().is_empty() => value
+            .split(',')
+            .map(|item| item.trim().trim_end_matches('/').to_ascii_lowercase())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        _ => defaults.iter().map(|item| item.to_string()).collect(),
+    }
+}
+
+// Characters that render like ASCII letters in common UI fonts
+fn confusable(c: char) -> char {
+    match c {
+        'а' | 'α' => 'a',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ε' => 'e',
+        'һ' => 'h',
+        'i' | 'і' | 'ι' | '1' | 'l' | '|' => 'l',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'о' | 'ο' | '0' => 'o',
+        'р' | 'ρ' => 'p',
+        'ѕ' => 's',
+        'т' | 'τ' => 't',
+        'ս' | 'υ' => 'u',
+        'ν' => 'v',
+        'ԝ' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'у' | 'γ' => 'y',
+        other => other,
+    }
+}
+
+// Collapses a host to the shape a reader sees, so "gіthub.com" and "g1thub.com" both
+// land on the same skeleton as "github.com"
+fn skeleton(host: &str) -> String {
+    host.to_lowercase()
+        .chars()
+        .map(confusable)
+        .collect::<String>()
+        .replace("rn", "m")
+        .replace("vv", "w")
+}
+
+fn within(host: &str, domain: &str) -> bool {
+    host == domain || host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.'))
+}
+
+pub struct ExternalLinks {
+    config: ExternalLinkConfig,
+}
+
+impl ExternalLinks {
+    pub fn from_env() -> Self {
+        let mut auth_origins = env_list(AUTH_ORIGINS_VAR, DEFAULT_AUTH_ORIGINS);
+        if cfg!(debug_assertions) {
+            auth_origins.extend(DEV_AUTH_ORIGINS.iter().map(|origin| origin.to_string()));
+        }
+
+        // Auth hosts are always safe to open as plain links
+        let mut trusted_domains = env_list(TRUSTED_DOMAINS_VAR, DEFAULT_TRUSTED_DOMAINS);
+        for origin in &auth_origins {
+            if let Some(host) = Url::parse(origin).ok().and_then(|url| url.host_str().map(str::to_string)) {
+                if !trusted_domains.contains(&host) {
+                    trusted_domains.push(host);
+                }
+            }
+        }
+
+        Self {
+            config: ExternalLinkConfig {
+                auth_origins,
+                trusted_domains,
+            },
+        }
+    }
+
+    fn parse(raw: &str) -> Result<Url, ExternalLinkError> {
+        let url = Url::parse(raw).map_err(|e| ExternalLinkError::InvalidUrl(e.to_string()))?;
+        if !matches!(url.scheme(), "https" | "http") {
+            return Err(ExternalLinkError::Blocked(format!("{} links cannot be opened", url.scheme())));
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(ExternalLinkError::Blocked("links with credentials cannot be opened".to_string()));
+        }
+        if url.host().is_none() {
+            return Err(ExternalLinkError::InvalidUrl("link has no host".to_string()));
+        }
+        Ok(url)
+    }
+
+    pub fn assess(&self, raw: &str, purpose: LinkPurpose) -> Result<LinkAssessment, ExternalLinkError> {
+        let url = Self::parse(raw)?;
+        // `Url` keeps hosts in their ASCII (punycode) form
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let (display_host, _) = idna::domain_to_unicode(&host);
+        let punycode = host.split('.').any(|label| label.starts_with("xn--"));
+
+        if purpose == LinkPurpose::Auth {
+            let origin = url.origin().ascii_serialization();
+            if !self.config.auth_origins.contains(&origin) {
+                return Err(ExternalLinkError::Blocked(format!("{} is not an allowed sign-in origin", origin)));
+            }
+            return Ok(LinkAssessment {
+                url: url.to_string(),
+                verdict: LinkVerdict::Open,
+                display_host,
+                punycode,
+                warnings: Vec::new(),
+            });
+        }
+
+        let trusted = self
+            .config
+            .trusted_domains
+            .iter()
+            .any(|domain| within(&host, domain));
+        let mut warnings = Vec::new();
+        if !trusted {
+            // Digits and look-alike letters also occur in honest names, so this only warns;
+            // the warning names the real host next to the trusted one it resembles
+            let shape = skeleton(&display_host);
+            match self
+                .config
+                .trusted_domains
+                .iter()
+                .find(|domain| within(&shape, &skeleton(domain)))
+            {
+                Some(domain) => warnings.push(format!(
+                    "{} looks like {} but is a different site ({})",
+                    display_host, domain, host
+                )),
+                None => warnings.push(format!("{} is not a known domain", display_host)),
+            }
+        }
+        if punycode {
+            warnings.push(format!("{} uses international characters and is really {}", display_host, host));
+        }
+        if url.scheme() == "http" {
+            warnings.push("the connection is not encrypted".to_string());
+        }
+        if matches!(url.host(), Some(Host::Ipv4(_) | Host::Ipv6(_))) {
+            warnings.push("the link points at a raw IP address".to_string());
+        }
+
+        Ok(LinkAssessment {
+            url: url.to_string(),
+            verdict: if warnings.is_empty() { LinkVerdict::Open } else { LinkVerdict::Prompt },
+            display_host,
+            punycode,
+            warnings,
+        })
+    }
+
+    async fn confirm(app: &AppHandle, assessment: &LinkAssessment) -> bool {
+        let (tx, rx) = oneshot::channel();
+        let message = format!(
+            "{}\n\nOnly continue if you trust this site:\n{}",
+            assessment
+                .warnings
+                .iter()
+                .map(|warning| format!("• {}", warning))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            assessment.url
+        );
+        app.dialog()
+            .message(message)
+            .title("Open external link?")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom("Open".to_string(), "Cancel".to_string()))
+            .show(move |open| {
+                let _ = tx.send(open);
+            });
+        // A dropped sender means the dialog went away, which we treat like a cancel
+        rx.await.unwrap_or(false)
+    }
+
+    // The only path from the shell to the system browser
+    pub async fn open(&self, app: &AppHandle, raw: &str, purpose: LinkPurpose) -> Result<(), ExternalLinkError> {
+        let assessment = self.assess(raw, purpose).inspect_err(|e| {
+            log::warn!("Refused to open external link: {}", e);
+        })?;
+
+        if assessment.verdict == LinkVerdict::Prompt && !Self::confirm(app, &assessment).await {
+            return Err(ExternalLinkError::Declined(assessment.display_host));
+        }
+
+        log::info!("Opening external link to {}", assessment.display_host);
+        tauri_plugin_opener::open_url(&assessment.url, None::<&str>)
+            .map_err(|e| ExternalLinkError::OpenFailed(e.to_string()))
+    }
+}
+
+// Tauri commands
+#[command]
+pub async fn open_external(
+    url: String,
+    purpose: Option<LinkPurpose>,
+    app: AppHandle,
+    links: State<'_, ExternalLinks>,
+) -> Result<(), CommandError> {
+    links
+        .open(&app, &url, purpose.unwrap_or(LinkPurpose::Link))
+        .await
+        .map_err(CommandError::from)
+}
+
+#[command]
+pub async fn check_external_link(
+    url: String,
+    links: State<'_, ExternalLinks>,
+) -> Result<LinkAssessment, CommandError> {
+    links.assess(&url, LinkPurpose::Link).map_err(CommandError::from)
+}
+
+#[command]
+pub async fn get_external_link_policy(links: State<'_, ExternalLinks>) -> Result<ExternalLinkConfig, CommandError> {
+    Ok(links.config.clone())
+}
@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use base64::{engine::general_purpose, Engine as _};
+use thiserror::Error;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+use crate::error::CommandError;
+
+// Upper bound for a single chunk crossing the IPC bridge
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+const SNIFF_LEN: usize = 8192;
+
+#[derive(Error, Debug)]
+pub enum FileError {
+    #[error("Unknown file handle: {0}")]
+    UnknownHandle(String),
+    #[error("File handle is not open for {0}")]
+    AccessDenied(String),
+    #[error("Chunk too large: {0} bytes")]
+    ChunkTooLarge(usize),
+    #[error("Out-of-order write at offset {0}")]
+    OutOfOrderWrite(u64),
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+    #[error("Rejected by policy: {0}")]
+    PolicyViolation(String),
+    #[error("Invalid artifact: {0}")]
+    InvalidArtifact(String),
+    #[error("Encoding error: {0}")]
+    EncodingError(String),
+    #[error("File I/O error: {0}")]
+    IoError(String),
+}
+
+impl From<std::io::Error> for FileError {
+    fn from(e: std::io::Error) -> Self {
+        FileError::IoError(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileAccess {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileHandle {
+    pub id: String,
+    pub name: String,
+    pub size: u64,
+    pub mime_type: String,
+    pub access: FileAccess,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChunk {
+    pub offset: u64,
+    pub data: String,
+    pub bytes_read: usize,
+    pub eof: bool,
+}
+
+// Subset of the frontend `Artifact` union needed to write it in its native format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactExport {
+    #[serde(rename = "type")]
+    pub artifact_type: String,
+    pub title: String,
+    #[serde(default)]
+    pub content: String,
+    pub language: Option<String>,
+    pub format: Option<String>,
+    pub base64: Option<String>,
+    pub data: Option<Vec<serde_json::Map<String, serde_json::Value>>>,
+    pub columns: Option<Vec<ArtifactColumn>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactColumn {
+    pub key: String,
+    pub label: String,
+}
+
+struct ScopedFile {
+    path: PathBuf,
+    access: FileAccess,
+    // Writes land here first and are renamed over `path` on commit
+    staging: Option<PathBuf>,
+    written: u64,
+}
+
+#[derive(Default)]
+pub struct FileManager {
+    handles: Mutex<HashMap<String, ScopedFile>>,
+}
+
+impl FileManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn register(&self, path: PathBuf, access: FileAccess) -> Result<FileHandle, FileError> {
+        let (size, mime_type) = match access {
+            FileAccess::Read => (fs::metadata(&path)?.len(), sniff_mime_type(&path)?),
+            FileAccess::Write => (0, mime_from_extension(&path).to_string()),
+        };
+
+        let id = Uuid::new_v4().to_string();
+        let handle = FileHandle {
+            id: id.clone(),
+            name: file_name(&path)?,
+            size,
+            mime_type,
+            access,
+        };
+
+        self.handles.lock().unwrap().insert(
+            id,
+            ScopedFile {
+                path,
+                access,
+                staging: None,
+                written: 0,
+            },
+        );
+        Ok(handle)
+    }
+
+    pub fn read_chunk(&self, handle_id: &str, offset: u64, length: usize) -> Result<FileChunk, FileError> {
+        if length > MAX_CHUNK_SIZE {
+            return Err(FileError::ChunkTooLarge(length));
+        }
+
+        let path = {
+            let handles = self.handles.lock().unwrap();
+            let scoped = handles
+                .get(handle_id)
+                .ok_or_else(|| FileError::UnknownHandle(handle_id.to_string()))?;
+            if scoped.access != FileAccess::Read {
+                return Err(FileError::AccessDenied("reading".to_string()));
+            }
+            scoped.path.clone()
+        };
+
+        let mut file = File::open(&path)?;
+        let total = file.metadata()?.len();
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut buffer = Vec::with_capacity(length);
+        file.take(length as u64).read_to_end(&mut buffer)?;
+
+        Ok(FileChunk {
+            offset,
+            bytes_read: buffer.len(),
+            eof: offset + buffer.len() as u64 >= total,
+            data: general_purpose::STANDARD.encode(&buffer),
+        })
+    }
+
+    pub fn write_chunk(&self, handle_id: &str, offset: u64, data: &[u8]) -> Result<u64, FileError> {
+        if data.len() > MAX_CHUNK_SIZE {
+            return Err(FileError::ChunkTooLarge(data.len()));
+        }
+
+        let mut handles = self.handles.lock().unwrap();
+        let scoped = handles
+            .get_mut(handle_id)
+            .ok_or_else(|| FileError::UnknownHandle(handle_id.to_string()))?;
+        if scoped.access != FileAccess::Write {
+            return Err(FileError::AccessDenied("writing".to_string()));
+        }
+        // Chunks are streamed sequentially; anything else would leave holes in the staged file
+        if offset != scoped.written {
+            return Err(FileError::OutOfOrderWrite(offset));
+        }
+
+        let staging = match &scoped.staging {
+            Some(staging) => staging.clone(),
+            None => {
+                let staging = staging_path(&scoped.path)?;
+                scoped.staging = Some(staging.clone());
+                staging
+            }
+        };
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&staging)?;
+        file.write_all(data)?;
+        scoped.written += data.len() as u64;
+        Ok(scoped.written)
+    }
+
+    pub fn commit(&self, handle_id: &str) -> Result<FileHandle, FileError> {
+        let scoped = {
+            let mut handles = self.handles.lock().unwrap();
+            match handles.get(handle_id) {
+                Some(scoped) if scoped.access != FileAccess::Write => {
+                    return Err(FileError::AccessDenied("writing".to_string()));
+                }
+                Some(_) => handles.remove(handle_id).unwrap(),
+                None => return Err(FileError::UnknownHandle(handle_id.to_string())),
+            }
+        };
+
+        match &scoped.staging {
+            Some(staging) => commit_staged(staging, &scoped.path)?,
+            // Nothing was streamed: the user saved an empty file
+            None => write_atomic(&scoped.path, &[])?,
+        }
+
+        Ok(FileHandle {
+            id: handle_id.to_string(),
+            name: file_name(&scoped.path)?,
+            size: scoped.written,
+            mime_type: sniff_mime_type(&scoped.path)?,
+            access: FileAccess::Write,
+        })
+    }
+
+    pub fn close(&self, handle_id: &str) {
+        if let Some(scoped) = self.handles.lock().unwrap().remove(handle_id) {
+            if let Some(staging) = scoped.staging {
+                let _ = fs::remove_file(staging);
+            }
+        }
+    }
+}
+
+// Write `data` next to `path` and rename it into place so readers never see a partial file
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), FileError> {
+    let staging = staging_path(path)?;
+    let result = (|| {
+        let mut file = File::create(&staging)?;
+        file.write_all(data)?;
+        drop(file);
+        commit_staged(&staging, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&staging);
+    }
+    result
+}
+
+fn commit_staged(staging: &Path, path: &Path) -> Result<(), FileError> {
+    File::open(staging)?.sync_all()?;
+    fs::rename(staging, path)?;
+
+    // Persist the rename itself; directories cannot be opened for syncing on Windows
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+fn staging_path(path: &Path) -> Result<PathBuf, FileError> {
+    let name = file_name(path)?;
+    let parent = path
+        .parent()
+        .ok_or_else(|| FileError::InvalidPath(path.display().to_string()))?;
+    Ok(parent.join(format!(".{}.{}.partial", name, Uuid::new_v4())))
+}
+
+fn file_name(path: &Path) -> Result<String, FileError> {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| FileError::InvalidPath(path.display().to_string()))
+}
+
+pub fn sniff_mime_type(path: &Path) -> Result<String, FileError> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut header)?;
+    Ok(sniff_bytes(&header, path).to_string())
+}
+
+pub fn sniff_bytes(header: &[u8], path: &Path) -> &'static str {
+    // Magic numbers win over the extension, which is only a hint for text formats
+    if let Some(kind) = infer::get(header) {
+        return kind.mime_type();
+    }
+
+    match mime_from_extension(path) {
+        "application/octet-stream" if std::str::from_utf8(header).is_ok() => "text/plain",
+        mime => mime,
+    }
+}
+
+pub fn mime_from_extension(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" | "jsx" => "text/javascript",
+        "ts" | "tsx" => "text/typescript",
+        "py" => "text/python",
+        "rs" => "text/rust",
+        "go" => "text/go",
+        "java" => "text/java",
+        "c" | "h" => "text/c",
+        "cpp" | "hpp" | "cc" => "text/cpp",
+        "sql" => "application/sql",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+fn extension_for_language(language: &str) -> &'static str {
+    match language.to_lowercase().as_str() {
+        "typescript" | "ts" => "ts",
+        "tsx" => "tsx",
+        "javascript" | "js" => "js",
+        "jsx" => "jsx",
+        "python" | "py" => "py",
+        "rust" | "rs" => "rs",
+        "go" => "go",
+        "java" => "java",
+        "c" => "c",
+        "cpp" | "c++" => "cpp",
+        "csharp" | "c#" => "cs",
+        "ruby" => "rb",
+        "php" => "php",
+        "swift" => "swift",
+        "kotlin" => "kt",
+        "sql" => "sql",
+        "html" => "html",
+        "css" => "css",
+        "json" => "json",
+        "shell" | "bash" | "sh" => "sh",
+        _ => "txt",
+    }
+}
+
+impl ArtifactExport {
+    fn extension(&self) -> &'static str {
+        match self.artifact_type.as_str() {
+            "code" | "react-component" | "python-script" | "sql-query" => {
+                extension_for_language(self.language.as_deref().unwrap_or_default())
+            }
+            "document" => match self.format.as_deref() {
+                Some("html") => "html",
+                Some("plain") => "txt",
+                _ => "md",
+            },
+            "markdown" => "md",
+            "html" => "html",
+            "spreadsheet" | "csv" => "csv",
+            "json" => "json",
+            "image" => match self.format.as_deref() {
+                Some("jpg") => "jpg",
+                Some("svg") => "svg",
+                Some("gif") => "gif",
+                _ => "png",
+            },
+            _ => "txt",
+        }
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, FileError> {
+        match self.artifact_type.as_str() {
+            "image" if self.format.as_deref() != Some("svg") => {
+                let encoded = self
+                    .base64
+                    .as_deref()
+                    .ok_or_else(|| FileError::InvalidArtifact("image has no base64 data".to_string()))?;
+                // Accept both raw base64 and `data:` URLs
+                let encoded = encoded.split_once(',').map(|(_, data)| data).unwrap_or(encoded);
+                general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|e| FileError::EncodingError(e.to_string()))
+            }
+            "spreadsheet" => match (&self.columns, &self.data) {
+                (Some(columns), Some(rows)) => Ok(rows_to_csv(columns, rows).into_bytes()),
+                _ => Ok(self.content.clone().into_bytes()),
+            },
+            _ => Ok(self.content.clone().into_bytes()),
+        }
+    }
+
+    fn suggested_file_name(&self) -> String {
+        let stem: String = self
+            .title
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+            .collect();
+        let stem = stem.trim_matches('-');
+        let stem = if stem.is_empty() { "artifact" } else { stem };
+        format!("{}.{}", stem, self.extension())
+    }
+}
+
+fn rows_to_csv(columns: &[ArtifactColumn], rows: &[serde_json::Map<String, serde_json::Value>]) -> String {
+    let mut csv = columns
+        .iter()
+        .map(|c| csv_field(&c.label))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+
+    for row in rows {
+        let line = columns
+            .iter()
+            .map(|c| match row.get(&c.key) {
+                Some(serde_json::Value::String(s)) => csv_field(s),
+                Some(serde_json::Value::Null) | None => String::new(),
+                Some(value) => csv_field(&value.to_string()),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(&line);
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn file_dialog(app: &AppHandle, filters: &Option<Vec<FileFilter>>) -> tauri_plugin_dialog::FileDialogBuilder<tauri::Wry> {
+    let mut dialog = app.dialog().file();
+    for filter in filters.iter().flatten() {
+        let extensions: Vec<&str> = filter.extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(&filter.name, &extensions);
+    }
+    dialog
+}
+
+fn into_path(file_path: FilePath) -> Result<PathBuf, FileError> {
+    file_path
+        .into_path()
+        .map_err(|e| FileError::InvalidPath(e.to_string()))
+}
+
+// Tauri commands
+#[command]
+pub async fn open_file_dialog(
+    filters: Option<Vec<FileFilter>>,
+    multiple: Option<bool>,
+    app: AppHandle,
+    file_manager: State<'_, FileManager>,
+) -> Result<Vec<FileHandle>, CommandError> {
+    let (tx, rx) = oneshot::channel();
+    let dialog = file_dialog(&app, &filters);
+    if multiple.unwrap_or(false) {
+        dialog.pick_files(move |paths| {
+            let _ = tx.send(paths.unwrap_or_default());
+        });
+    } else {
+        dialog.pick_file(move |path| {
+            let _ = tx.send(path.into_iter().collect());
+        });
+    }
+
+    // A dropped sender means the dialog went away, which we treat like a cancel
+    let picked = rx.await.unwrap_or_default();
+    picked
+        .into_iter()
+        .map(|p| file_manager.register(into_path(p)?, FileAccess::Read))
+        .collect::<Result<_, FileError>>()
+        .map_err(CommandError::from)
+}
+
+#[command]
+pub async fn save_file_dialog(
+    default_name: Option<String>,
+    filters: Option<Vec<FileFilter>>,
+    app: AppHandle,
+    file_manager: State<'_, FileManager>,
+) -> Result<Option<FileHandle>, CommandError> {
+    let (tx, rx) = oneshot::channel();
+    let mut dialog = file_dialog(&app, &filters);
+    if let Some(name) = default_name {
+        dialog = dialog.set_file_name(name);
+    }
+    dialog.save_file(move |path| {
+        let _ = tx.send(path);
+    });
+
+    match rx.await.ok().flatten() {
+        Some(path) => Ok(Some(file_manager.register(into_path(path)?, FileAccess::Write)?)),
+        None => Ok(None),
+    }
+}
+
+#[command]
+pub async fn read_file_chunk(
+    handle_id: String,
+    offset: u64,
+    length: usize,
+    file_manager: State<'_, FileManager>,
+) -> Result<FileChunk, CommandError> {
+    file_manager.read_chunk(&handle_id, offset, length).map_err(CommandError::from)
+}
+
+#[command]
+pub async fn write_file_chunk(
+    handle_id: String,
+    offset: u64,
+    data: String,
+    file_manager: State<'_, FileManager>,
+) -> Result<u64, CommandError> {
+    let bytes = general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| FileError::EncodingError(e.to_string()))?;
+    file_manager.write_chunk(&handle_id, offset, &bytes).map_err(CommandError::from)
+}
+
+#[command]
+pub async fn commit_file_handle(
+    handle_id: String,
+    file_manager: State<'_, FileManager>,
+) -> Result<FileHandle, CommandError> {
+    file_manager.commit(&handle_id).map_err(CommandError::from)
+}
+
+#[command]
+pub async fn close_file_handle(
+    handle_id: String,
+    file_manager: State<'_, FileManager>,
+) -> Result<(), CommandError> {
+    file_manager.close(&handle_id);
+    Ok(())
+}
+
+#[command]
+pub async fn save_artifact(
+    artifact: ArtifactExport,
+    app: AppHandle,
+) -> Result<Option<FileHandle>, CommandError> {
+    let bytes = artifact.to_bytes()?;
+    let extension = artifact.extension();
+
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_file_name(artifact.suggested_file_name())
+        .add_filter(extension.to_uppercase(), &[extension])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = into_path(path)?;
+    write_atomic(&path, &bytes)?;
+
+    Ok(Some(FileHandle {
+        id: Uuid::new_v4().to_string(),
+        name: file_name(&path)?,
+        size: bytes.len() as u64,
+        mime_type: mime_from_extension(&path).to_string(),
+        access: FileAccess::Write,
+    }))
+}
@@ -0,0 +1,363 @@
+use tauri::ipc::Invoke;
+use tauri::{Manager, Runtime};
+use thiserror::Error;
+use crate::deep_link_policy::redact;
+use crate::error::CommandError;
+use crate::navigation::{NavigationGuard, MAIN_WINDOW};
+use crate::scopes::{self, LOCAL_TOOLS, SYNC};
+use crate::vault::Vault;
+
+#[derive(Error, Debug)]
+pub enum GuardError {
+    #[error("{command} may not be called from window {label}")]
+    WrongWindow { command: String, label: String },
+    #[error("{command} may not be called from {origin}")]
+    WrongOrigin { command: String, origin: String },
+    #[error("{0} requires the vault to be unlocked")]
+    VaultLocked(String),
+    #[error("{0} requires an active session")]
+    SessionRequired(String),
+    #[error("{command} requires the {} scope", .missing.join(", "))]
+    MissingScopes { command: String, missing: Vec<String> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Requirement {
+    None,
+    // The vault has not been locked by the user or the OS
+    Unlocked,
+    // Unlocked, and unlocked with a verified token that has not expired
+    Session,
+}
+
+struct CommandPolicy {
+    command: &'static str,
+    windows: &'static [&'static str],
+    requirement: Requirement,
+    // All of these must be granted to the active session
+    scopes: &'static [&'static str],
+}
+
+impl CommandPolicy {
+    const fn new(command: &'static str, windows: &'static [&'static str], requirement: Requirement) -> Self {
+        Self {
+            command,
+            windows,
+            requirement,
+            scopes: &[],
+        }
+    }
+
+    // Checked against the active session. Without one only the requirement applies, so
+    // commands that also make sense signed out stay at Unlocked.
+    const fn scoped(self, scopes: &'static [&'static str]) -> Self {
+        Self { scopes, ..self }
+    }
+}
+
+const MAIN_ONLY: &[&str] = &[MAIN_WINDOW];
+// Auxiliary windows are labelled aux-* (see capabilities/auxiliary.json)
+const ANY_WINDOW: &[&str] = &[MAIN_WINDOW, "aux-*"];
+
+// Commands that are not listed here get DEFAULT_POLICY, so anything new stays behind the lock
+const POLICIES: &[CommandPolicy] = &[
+    CommandPolicy::new("rotate_auth_store_key", MAIN_ONLY, Requirement::Session),
+    CommandPolicy::new("confirm_share_import", MAIN_ONLY, Requirement::Session).scoped(&[SYNC]),
+    // Entry points to the local filesystem; chunk reads and writes need a handle from these.
+    // They work signed out, but a signed-in session must carry local_tools.
+    CommandPolicy::new("open_file_dialog", MAIN_ONLY, Requirement::Unlocked).scoped(&[LOCAL_TOOLS]),
+    CommandPolicy::new("save_file_dialog", MAIN_ONLY, Requirement::Unlocked).scoped(&[LOCAL_TOOLS]),
+    CommandPolicy::new("save_artifact", MAIN_ONLY, Requirement::Unlocked).scoped(&[LOCAL_TOOLS]),
+    // Locking is always safe and must work from wherever the user is
+    CommandPolicy::new("lock_vault", ANY_WINDOW, Requirement::None),
+    CommandPolicy::new("get_vault_status", ANY_WINDOW, Requirement::None),
+    CommandPolicy::new("get_error_catalogue", ANY_WINDOW, Requirement::None),
+    CommandPolicy::new("get_auth_store_recovery", MAIN_ONLY, Requirement::None),
+    // Unlocking: with a verified token, or by signing in again. The sign-in needs its
+    // callback link, and the exchange only unlocks once the backend has issued a token.
+    CommandPolicy::new("unlock_vault", MAIN_ONLY, Requirement::None),
+    CommandPolicy::new("generate_auth_session", MAIN_ONLY, Requirement::None),
+    CommandPolicy::new("open_auth_url", MAIN_ONLY, Requirement::None),
+    CommandPolicy::new("deep_link_ready", MAIN_ONLY, Requirement::None),
+    CommandPolicy::new("drain_deep_links", MAIN_ONLY, Requirement::None),
+    CommandPolicy::new("ack_deep_link", MAIN_ONLY, Requirement::None),
+    CommandPolicy::new("exchange_auth_code", MAIN_ONLY, Requirement::None),
+];
+
+const DEFAULT_POLICY: CommandPolicy = CommandPolicy::new("*", MAIN_ONLY, Requirement::Unlocked);
+
+fn label_matches(pattern: &str, label: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => label.starts_with(prefix),
+        None => pattern == label,
+    }
+}
+
+fn policy_for(command: &str) -> &'static CommandPolicy {
+    POLICIES
+        .iter()
+        .find(|policy| policy.command == command)
+        .unwrap_or(&DEFAULT_POLICY)
+}
+
+fn check_window(policy: &CommandPolicy, command: &str, label: &str) -> Result<(), GuardError> {
+    if !policy.windows.iter().any(|pattern| label_matches(pattern, label)) {
+        return Err(GuardError::WrongWindow {
+            command: command.to_string(),
+            label: label.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn check_vault(policy: &CommandPolicy, command: &str, vault: &Vault) -> Result<(), GuardError> {
+    if policy.requirement != Requirement::None && vault.is_locked() {
+        return Err(GuardError::VaultLocked(command.to_string()));
+    }
+    // Scopes are granted to a session, so a scoped command needs one too
+    let Some(session) = vault.active_session() else {
+        if policy.requirement == Requirement::Session || !policy.scopes.is_empty() {
+            return Err(GuardError::SessionRequired(command.to_string()));
+        }
+        return Ok(());
+    };
+    let missing = scopes::missing(&session.scopes, policy.scopes);
+    if !missing.is_empty() {
+        return Err(GuardError::MissingScopes {
+            command: command.to_string(),
+            missing: missing.into_iter().map(str::to_string).collect(),
+        });
+    }
+    Ok(())
+}
+
+fn authorize<R: Runtime>(invoke: &Invoke<R>) -> Result<(), GuardError> {
+    let command = invoke.message.command();
+    let webview = invoke.message.webview();
+    let policy = policy_for(command);
+    check_window(policy, command, webview.label())?;
+
+    // A webview that navigated away from the app must not keep its IPC access
+    let app = webview.app_handle();
+    let url = webview.url().ok();
+    if !url.as_ref().is_some_and(|url| app.state::<NavigationGuard>().is_app_origin(url)) {
+        return Err(GuardError::WrongOrigin {
+            command: command.to_string(),
+            origin: url.map(|url| redact(url.as_str())).unwrap_or_else(|| "<unknown>".to_string()),
+        });
+    }
+
+    check_vault(policy, command, &app.state::<Vault>())
+}
+
+// Wraps the generated invoke handler; denied calls are rejected here and never reach
+// the command. Plugin commands are dispatched by their plugins and do not pass through.
+pub fn guarded<R, F>(handler: F) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static
+where
+    R: Runtime,
+    F: Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+{
+    move |invoke| match authorize(&invoke) {
+        Ok(()) => handler(invoke),
+        Err(e) => {
+            log::warn!("Denied IPC call: {}", e);
+            invoke.resolver.reject(CommandError::from(e));
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use ed25519_dalek::SigningKey;
+    use serde_json::json;
+    use crate::auth::{exchange_code, AuthToken, DeviceInfo};
+    use crate::backend::stand_in::{self, Recorded, Reply};
+    use crate::dpop::DeviceKey;
+    use crate::jwt::{JwtValidator, VerifiedClaims};
+    use crate::vault::VaultSession;
+    use super::*;
+
+    fn token(scope: Option<&str>) -> AuthToken {
+        AuthToken {
+            access_token: "synthetic.access.token".to_string(),
+            refresh_token: "synthetic-refresh".to_string(),
+            expires_at: Utc::now() + Duration::hours(1),
+            token_type: "Bearer".to_string(),
+            scope: scope.map(str::to_string),
+        }
+    }
+
+    fn claims(scopes: &[&str]) -> VerifiedClaims {
+        VerifiedClaims {
+            user_id: "user_1".to_string(),
+            email: None,
+            wallet_address: None,
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            issuer: "symlog-auth".to_string(),
+            issued_at: Some(Utc::now()),
+            expires_at: Utc::now() + Duration::hours(1),
+            device_bound: true,
+        }
+    }
+
+    // What unlock_vault does once the token has been verified
+    fn signed_in(token: &AuthToken, claims: &VerifiedClaims) -> Vault {
+        let vault = Vault::new();
+        vault.unlock(
+            VaultSession {
+                user_id: claims.user_id.clone(),
+                scopes: scopes::granted(token, claims),
+                unlocked_at: Utc::now(),
+                expires_at: claims.expires_at,
+            },
+            &token.access_token,
+        );
+        vault
+    }
+
+    fn check(command: &str, vault: &Vault) -> Result<(), GuardError> {
+        check_vault(policy_for(command), command, vault)
+    }
+
+    #[test]
+    fn granted_scopes_never_exceed_the_signed_claims() {
+        let signed = claims(&[LOCAL_TOOLS, SYNC]);
+        assert_eq!(scopes::granted(&token(None), &signed), vec![LOCAL_TOOLS, SYNC]);
+        assert_eq!(scopes::granted(&token(Some("sync")), &signed), vec![SYNC]);
+        assert_eq!(
+            scopes::granted(&token(Some("sync wallet:sign")), &claims(&[SYNC])),
+            vec![SYNC]
+        );
+        assert!(scopes::granted(&token(Some("")), &signed).is_empty());
+    }
+
+    #[test]
+    fn missing_lists_required_scopes_not_granted() {
+        let granted = vec![SYNC.to_string()];
+        assert_eq!(scopes::missing(&granted, &[LOCAL_TOOLS, SYNC]), vec![LOCAL_TOOLS]);
+        assert!(scopes::missing(&granted, &[SYNC]).is_empty());
+        assert!(scopes::missing(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn commands_are_refused_without_their_scopes() {
+        let vault = signed_in(&token(Some("sync")), &claims(&[LOCAL_TOOLS, SYNC]));
+        assert!(check("confirm_share_import", &vault).is_ok());
+        match check("open_file_dialog", &vault) {
+            Err(GuardError::MissingScopes { command, missing }) => {
+                assert_eq!(command, "open_file_dialog");
+                assert_eq!(missing, vec![LOCAL_TOOLS]);
+            }
+            other => panic!("expected MissingScopes, got {:?}", other),
+        }
+
+        let vault = signed_in(&token(None), &claims(&[LOCAL_TOOLS]));
+        assert!(check("save_artifact", &vault).is_ok());
+        assert!(matches!(
+            check("confirm_share_import", &vault),
+            Err(GuardError::MissingScopes { .. })
+        ));
+    }
+
+    #[test]
+    fn scoped_commands_need_a_session() {
+        let vault = Vault::new();
+        for command in ["open_file_dialog", "save_file_dialog", "save_artifact", "confirm_share_import"] {
+            assert!(
+                matches!(check(command, &vault), Err(GuardError::SessionRequired(_))),
+                "{}",
+                command
+            );
+        }
+        assert!(matches!(
+            check("rotate_auth_store_key", &vault),
+            Err(GuardError::SessionRequired(_))
+        ));
+        assert!(check("read_file_chunk", &vault).is_ok());
+    }
+
+    #[tokio::test]
+    async fn an_exchanged_token_is_held_to_its_scopes() {
+        let expires_at = (Utc::now() + Duration::minutes(15)).timestamp_millis();
+        let backend = stand_in::serve(move |request: &Recorded| match request.function() {
+            "auth:validateAuthCode" => Reply::value(json!({
+                "userId": "user_1",
+                "userEmail": null,
+                "walletAddress": null,
+                "session": {
+                    "accessToken": "synthetic.access.token",
+                    "refreshToken": "synthetic-refresh",
+                    "accessTokenExpiresAt": expires_at,
+                    "scope": "sync",
+                },
+            })),
+            "sessions:validateAccessToken" => Reply::value(json!({
+                "valid": true,
+                "userId": "user_1",
+                "sessionId": "s_current",
+                "accessTokenExpiresAt": expires_at,
+                "scope": "local_tools sync",
+            })),
+            other => panic!("unexpected call to {}", other),
+        })
+        .await;
+        let device = DeviceInfo {
+            device_id: "device-1".to_string(),
+            device_name: "test".to_string(),
+            platform: "linux".to_string(),
+            user_agent: None,
+        };
+        let device_key = DeviceKey::from_signing_key(SigningKey::from_bytes(&[7u8; 32]));
+        let vault = Vault::new();
+
+        let grant = exchange_code(
+            "code",
+            "verifier",
+            &device,
+            &device_key,
+            &backend.client,
+            &JwtValidator::new(None),
+            &vault,
+        )
+        .await
+        .unwrap();
+        assert_eq!(grant.token.scope.as_deref(), Some("sync"));
+
+        // The token response narrows the introspected grant
+        assert!(check("confirm_share_import", &vault).is_ok());
+        assert!(matches!(
+            check("save_artifact", &vault),
+            Err(GuardError::MissingScopes { .. })
+        ));
+    }
+
+    #[test]
+    fn a_locked_vault_only_answers_status_and_unlock_commands() {
+        // As at startup with a stored session
+        let vault = Vault::locked();
+        for command in [
+            "factory_reset_auth_store",
+            "sign_out_everywhere",
+            "refresh_auth_token",
+            "put_blob",
+            "release_conversation_blobs",
+            "read_file_chunk",
+            "write_file_chunk",
+            "unlisted",
+        ] {
+            assert!(matches!(check(command, &vault), Err(GuardError::VaultLocked(_))), "{}", command);
+        }
+        for command in ["get_vault_status", "lock_vault", "unlock_vault", "exchange_auth_code"] {
+            assert!(check(command, &vault).is_ok(), "{}", command);
+        }
+    }
+
+    #[test]
+    fn windows_are_matched_by_label() {
+        assert!(check_window(policy_for("lock_vault"), "lock_vault", "aux-preview").is_ok());
+        assert!(check_window(policy_for("save_artifact"), "save_artifact", "aux-preview").is_err());
+        assert!(check_window(policy_for("unlisted"), "unlisted", MAIN_WINDOW).is_ok());
+    }
+}
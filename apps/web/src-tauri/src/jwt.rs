@@ -0,0 +1,384 @@
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::json;
+use tokio::sync::RwLock;
+use crate::auth::AuthError;
+use crate::backend::BackendClient;
+use crate::dpop::DeviceKey;
+use crate::error::CommandError;
+
+// The Convex backend issues opaque HS256 session tokens without a key id, so there is no
+// issuer to verify against by default: tokens are introspected through the backend until
+// an asymmetric issuer is configured with SYMLOG_AUTH_ISSUER, SYMLOG_AUTH_AUDIENCE and
+// SYMLOG_JWKS_URL
+const DEFAULT_LEEWAY_SECS: u64 = 60;
+const JWKS_TTL: Duration = Duration::from_secs(60 * 60);
+// Unknown key ids trigger a refetch, but never more often than this
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+// Symmetric algorithms are refused outright so a public JWK can never act as an HMAC secret
+const ALLOWED_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtConfig {
+    pub issuer: String,
+    pub audience: String,
+    pub jwks_url: String,
+    pub leeway_secs: u64,
+}
+
+impl JwtConfig {
+    // None unless the issuer is fully configured; a partial config is ignored with a warning
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let (issuer, audience, jwks_url) = match (
+            var("SYMLOG_AUTH_ISSUER"),
+            var("SYMLOG_AUTH_AUDIENCE"),
+            var("SYMLOG_JWKS_URL"),
+        ) {
+            (Some(issuer), Some(audience), Some(jwks_url)) => (issuer, audience, jwks_url),
+            (None, None, None) => return None,
+            _ => {
+                log::warn!("Incomplete JWT issuer configuration; introspecting tokens through the backend");
+                return None;
+            }
+        };
+        Some(Self {
+            issuer,
+            audience,
+            jwks_url,
+            leeway_secs: var("SYMLOG_JWT_LEEWAY_SECS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_LEEWAY_SECS),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Confirmation {
+    jkt: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawClaims {
+    sub: String,
+    #[serde(alias = "user_id")]
+    user_id: Option<String>,
+    email: Option<String>,
+    #[serde(alias = "wallet_address")]
+    wallet_address: Option<String>,
+    scope: Option<String>,
+    scp: Option<Vec<String>>,
+    iss: String,
+    exp: i64,
+    iat: Option<i64>,
+    cnf: Option<Confirmation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IntrospectedUser {
+    email: Option<String>,
+    wallet_address: Option<String>,
+}
+
+// What `sessions:validateAccessToken` reports for a token
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Introspection {
+    valid: bool,
+    reason: Option<String>,
+    user_id: Option<String>,
+    access_token_expires_at: Option<f64>,
+    scope: Option<String>,
+    user: Option<IntrospectedUser>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifiedClaims {
+    pub user_id: String,
+    pub email: Option<String>,
+    pub wallet_address: Option<String>,
+    pub scopes: Vec<String>,
+    pub issuer: String,
+    pub issued_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+    pub device_bound: bool,
+}
+
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+pub struct JwtValidator {
+    config: Option<JwtConfig>,
+    client: reqwest::Client,
+    jwks: RwLock<Option<CachedJwks>>,
+}
+
+impl JwtValidator {
+    pub fn new(config: Option<JwtConfig>) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            jwks: RwLock::new(None),
+        }
+    }
+
+    async fn fetch_jwks(&self, config: &JwtConfig) -> Result<JwkSet, AuthError> {
+        let response = self
+            .client
+            .get(&config.jwks_url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+        response
+            .json::<JwkSet>()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))
+    }
+
+    async fn decoding_key(&self, config: &JwtConfig, kid: &str, alg: Algorithm) -> Result<DecodingKey, AuthError> {
+        let find = |keys: &JwkSet| -> Option<Result<DecodingKey, AuthError>> {
+            let jwk = keys.find(kid)?;
+            // A key pinned to one algorithm must not verify tokens claiming another
+            if let Some(key_alg) = jwk.common.key_algorithm {
+                if key_alg.to_string() != format!("{:?}", alg) {
+                    return Some(Err(AuthError::InvalidToken("algorithm does not match key".to_string())));
+                }
+            }
+            Some(DecodingKey::from_jwk(jwk).map_err(|e| AuthError::InvalidToken(e.to_string())))
+        };
+
+        let needs_refresh = {
+            let cached = self.jwks.read().await;
+            match cached.as_ref() {
+                Some(cached) => {
+                    let age = cached.fetched_at.elapsed();
+                    if age < JWKS_TTL {
+                        if let Some(key) = find(&cached.keys) {
+                            return key;
+                        }
+                        // Unknown kid on a fresh set usually means the issuer rotated keys
+                        age >= JWKS_MIN_REFRESH
+                    } else {
+                        true
+                    }
+                }
+                None => true,
+            }
+        };
+
+        if needs_refresh {
+            match self.fetch_jwks(config).await {
+                Ok(keys) => {
+                    *self.jwks.write().await = Some(CachedJwks {
+                        keys,
+                        fetched_at: Instant::now(),
+                    });
+                }
+                // A stale set is better than locking the user out during an issuer outage
+                Err(e) => log::warn!("Failed to refresh JWKS, using cached keys: {}", e),
+            }
+        }
+
+        let cached = self.jwks.read().await;
+        match cached.as_ref().and_then(|cached| find(&cached.keys)) {
+            Some(key) => key,
+            None if cached.is_none() => Err(AuthError::NetworkError("JWKS unavailable".to_string())),
+            None => Err(AuthError::InvalidToken(format!("unknown signing key {}", kid))),
+        }
+    }
+
+    pub async fn verify(
+        &self,
+        token: &str,
+        device_key: &DeviceKey,
+        backend: &BackendClient,
+    ) -> Result<VerifiedClaims, AuthError> {
+        match &self.config {
+            Some(config) => self.verify_signed(config, token, device_key).await,
+            None => introspect(backend, token, device_key).await,
+        }
+    }
+
+    async fn verify_signed(
+        &self,
+        config: &JwtConfig,
+        token: &str,
+        device_key: &DeviceKey,
+    ) -> Result<VerifiedClaims, AuthError> {
+        let header = decode_header(token).map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(AuthError::InvalidToken(format!("algorithm {:?} is not allowed", header.alg)));
+        }
+        let kid = header
+            .kid
+            .ok_or_else(|| AuthError::InvalidToken("token has no key id".to_string()))?;
+        let key = self.decoding_key(config, &kid, header.alg).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = config.leeway_secs;
+        validation.validate_nbf = true;
+        validation.set_issuer(&[&config.issuer]);
+        validation.set_audience(&[&config.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<RawClaims>(token, &key, &validation)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?
+            .claims;
+
+        // A DPoP-bound token is only valid together with this install's key
+        let bound_jkt = claims.cnf.and_then(|cnf| cnf.jkt);
+        if let Some(jkt) = &bound_jkt {
+            if jkt != device_key.thumbprint() {
+                return Err(AuthError::InvalidToken("token is bound to a different device".to_string()));
+            }
+        }
+
+        let scopes = match (claims.scope, claims.scp) {
+            (Some(scope), _) => scope.split_whitespace().map(str::to_string).collect(),
+            (None, Some(scp)) => scp,
+            (None, None) => Vec::new(),
+        };
+
+        Ok(VerifiedClaims {
+            user_id: claims.user_id.unwrap_or(claims.sub),
+            email: claims.email,
+            wallet_address: claims.wallet_address,
+            scopes,
+            issuer: claims.iss,
+            issued_at: claims.iat.and_then(|iat| Utc.timestamp_opt(iat, 0).single()),
+            expires_at: Utc
+                .timestamp_opt(claims.exp, 0)
+                .single()
+                .ok_or_else(|| AuthError::InvalidToken("invalid exp".to_string()))?,
+            device_bound: bound_jkt.is_some(),
+        })
+    }
+}
+
+// The backend's own view of the token: it must belong to an active, unexpired session
+async fn introspect(
+    backend: &BackendClient,
+    token: &str,
+    device_key: &DeviceKey,
+) -> Result<VerifiedClaims, AuthError> {
+    let status: Introspection = backend
+        .query(
+            "sessions:validateAccessToken",
+            json!({ "accessToken": token }),
+            token,
+            device_key,
+        )
+        .await?;
+    let (true, Some(user_id)) = (status.valid, status.user_id) else {
+        return Err(AuthError::InvalidToken(format!(
+            "access token has no active session ({})",
+            status.reason.as_deref().unwrap_or("unknown")
+        )));
+    };
+    let expires_at = status
+        .access_token_expires_at
+        .and_then(|millis| Utc.timestamp_millis_opt(millis as i64).single())
+        .ok_or_else(|| AuthError::InvalidToken("backend reported no token expiry".to_string()))?;
+    let user = status.user;
+
+    Ok(VerifiedClaims {
+        user_id,
+        email: user.as_ref().and_then(|user| user.email.clone()),
+        wallet_address: user.and_then(|user| user.wallet_address),
+        scopes: status
+            .scope
+            .as_deref()
+            .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+        issuer: backend.base_url().to_string(),
+        issued_at: None,
+        expires_at,
+        device_bound: false,
+    })
+}
+
+// Tauri commands
+#[command]
+pub async fn verify_access_token(
+    token: String,
+    validator: State<'_, JwtValidator>,
+    device_key: State<'_, DeviceKey>,
+    backend: State<'_, BackendClient>,
+) -> Result<VerifiedClaims, CommandError> {
+    validator
+        .verify(&token, &device_key, &backend)
+        .await
+        .map_err(CommandError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+    use crate::backend::stand_in::{self, Recorded, Reply};
+    use super::*;
+
+    const ACCESS_TOKEN: &str = "header.payload.signature";
+
+    fn device_key() -> DeviceKey {
+        DeviceKey::from_signing_key(SigningKey::from_bytes(&[7u8; 32]))
+    }
+
+    // The shape the backend actually issues: HS256, no key id, an opaque signature
+    #[tokio::test]
+    async fn backend_tokens_are_introspected_without_an_issuer() {
+        let backend = stand_in::serve(|_: &Recorded| {
+            Reply::value(json!({
+                "valid": true,
+                "userId": "user_1",
+                "sessionId": "s_current",
+                "accessTokenExpiresAt": 1_700_000_900_000.0,
+                "user": { "email": "a@example.com", "walletAddress": null },
+            }))
+        })
+        .await;
+
+        let claims = JwtValidator::new(None)
+            .verify(ACCESS_TOKEN, &device_key(), &backend.client)
+            .await
+            .unwrap();
+        assert_eq!(claims.user_id, "user_1");
+        assert_eq!(claims.email.as_deref(), Some("a@example.com"));
+        assert_eq!(claims.expires_at.timestamp_millis(), 1_700_000_900_000);
+        assert!(!claims.device_bound);
+
+        let requests = backend.requests();
+        assert_eq!(requests[0].function(), "sessions:validateAccessToken");
+        assert_eq!(requests[0].body["args"], json!({ "accessToken": ACCESS_TOKEN }));
+    }
+
+    #[tokio::test]
+    async fn tokens_without_an_active_session_are_rejected() {
+        for reason in ["token_not_found", "session_inactive", "token_expired"] {
+            let backend = stand_in::serve(move |_: &Recorded| Reply::value(json!({ "valid": false, "reason": reason }))).await;
+            let result = JwtValidator::new(None)
+                .verify(ACCESS_TOKEN, &device_key(), &backend.client)
+                .await;
+            assert!(matches!(result, Err(AuthError::InvalidToken(_))), "{}", reason);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use zeroize::Zeroizing;
+use crate::auth::AuthError;
+
+// Secrets that must never sit inside the store they protect. The OS keyring is used where
+// one answers; hosts without one (e.g. no Secret Service on a bare Linux session) fall back
+// to an owner-only file in the app's local data directory, still outside auth.json.
+//
+// The backend an account ends up in is recorded beside the key files once it holds a
+// secret. From then on the secret never moves: a keyring that stops answering is an
+// error, not a reason to start over in a file with fresh key material.
+pub struct KeyStorage {
+    backend: Backend,
+    marker: PathBuf,
+}
+
+enum Backend {
+    Keyring(keyring::Entry),
+    File(PathBuf),
+}
+
+const KEYRING_MARKER: &str = "keyring";
+const FILE_MARKER: &str = "file";
+// A keyring daemon that is still starting with the session gets a moment to answer
+const KEYRING_ATTEMPTS: u32 = 3;
+const KEYRING_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+// Probing tells a missing entry apart from a keyring that is not running
+fn keyring_entry(service: &str, account: &str) -> Result<keyring::Entry, keyring::Error> {
+    let mut attempt = 1;
+    loop {
+        let result = keyring::Entry::new(service, account).and_then(|entry| match entry.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(entry),
+            Err(e) => Err(e),
+        });
+        match result {
+            Err(e) if attempt < KEYRING_ATTEMPTS => {
+                log::debug!("OS keyring did not answer for {} (attempt {}): {}", account, attempt, e);
+                std::thread::sleep(KEYRING_RETRY_DELAY);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn read_marker(path: &Path) -> Result<Option<String>, AuthError> {
+    match fs::read_to_string(path) {
+        Ok(marker) => Ok(Some(marker.trim().to_string())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AuthError::StorageError(e.to_string())),
+    }
+}
+
+impl KeyStorage {
+    pub fn open(app: &AppHandle, account: &str) -> Result<Self, AuthError> {
+        let service = &app.config().identifier;
+        let dir = app
+            .path()
+            .app_local_data_dir()
+            .map_err(|e| AuthError::StorageError(e.to_string()))?;
+        let key_file = dir.join(format!("{}.key", account));
+        let marker = dir.join(format!("{}.backend", account));
+
+        let recorded = read_marker(&marker)?;
+        let backend = match recorded.as_deref() {
+            Some(KEYRING_MARKER) => keyring_entry(service, account).map(Backend::Keyring).map_err(|e| {
+                AuthError::KeyUnavailable(format!("{} is kept in the OS keyring, which is not answering: {}", account, e))
+            })?,
+            Some(FILE_MARKER) => Backend::File(key_file),
+            Some(other) => {
+                return Err(AuthError::StorageError(format!("{} names an unknown key storage: {}", marker.display(), other)))
+            }
+            // A key file written before the choice was recorded
+            None if key_file.exists() => Backend::File(key_file),
+            None => match keyring_entry(service, account) {
+                Ok(entry) => Backend::Keyring(entry),
+                Err(e) => {
+                    log::warn!("OS keyring is unavailable, keeping {} in a key file: {}", account, e);
+                    Backend::File(key_file)
+                }
+            },
+        };
+
+        let storage = Self { backend, marker };
+        if recorded.is_none() && storage.load()?.is_some() {
+            storage.record()?;
+        }
+        Ok(storage)
+    }
+
+    fn record(&self) -> Result<(), AuthError> {
+        let marker = match self.backend {
+            Backend::Keyring(_) => KEYRING_MARKER,
+            Backend::File(_) => FILE_MARKER,
+        };
+        write_private(&self.marker, marker).map_err(|e| AuthError::StorageError(e.to_string()))
+    }
+
+    pub fn load(&self) -> Result<Option<Zeroizing<String>>, AuthError> {
+        match &self.backend {
+            Backend::Keyring(entry) => match entry.get_password() {
+                Ok(secret) => Ok(Some(Zeroizing::new(secret))),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(AuthError::StorageError(e.to_string())),
+            },
+            Backend::File(path) => match fs::read_to_string(path) {
+                Ok(secret) => Ok(Some(Zeroizing::new(secret))),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(AuthError::StorageError(e.to_string())),
+            },
+        }
+    }
+
+    pub fn save(&self, secret: &str) -> Result<(), AuthError> {
+        match &self.backend {
+            Backend::Keyring(entry) => entry
+                .set_password(secret)
+                .map_err(|e| AuthError::StorageError(e.to_string())),
+            Backend::File(path) => write_private(path, secret).map_err(|e| AuthError::StorageError(e.to_string())),
+        }?;
+        self.record()
+    }
+
+    pub fn delete(&self) -> Result<(), AuthError> {
+        match &self.backend {
+            Backend::Keyring(entry) => match entry.delete_credential() {
+                Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(AuthError::StorageError(e.to_string())),
+            },
+            Backend::File(path) => match fs::remove_file(path) {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(AuthError::StorageError(e.to_string())),
+            },
+        }
+    }
+}
+
+// Written beside the target and renamed over it, so a crash never leaves half a key
+fn write_private(path: &Path, secret: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut staging = path.as_os_str().to_owned();
+    staging.push(".tmp");
+    let staging = PathBuf::from(staging);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&staging)?;
+    file.write_all(secret.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&staging, path)
+}
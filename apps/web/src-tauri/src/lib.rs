@@ -1,11 +1,55 @@
 use std::env;
-use tauri::{Manager, Emitter, Listener};
+use tauri::Manager;
 
+mod activity;
+mod asset_protocol;
+mod attachments;
 mod auth;
+mod auth_recovery;
+mod backend;
+mod blob_store;
 mod deep_link;
+mod deep_link_policy;
+mod device;
+mod dpop;
+mod error;
+mod external_links;
+mod files;
+mod ipc_guard;
+mod jwt;
+mod keychain;
+#[cfg(target_os = "linux")]
+mod logind;
+mod migrations;
+mod navigation;
+mod remote_sessions;
+mod scopes;
+mod security;
+mod share;
+mod vault;
+#[cfg(target_os = "linux")]
+mod xdg;
 
-use auth::{AuthManager, generate_auth_session, handle_auth_callback, clear_auth_session, clear_all_auth_sessions, get_auth_session};
-use deep_link::{setup_deep_linking, open_auth_url, register_auth_protocol, get_current_deep_link};
+use activity::{BackgroundActivity, get_background_activity};
+use asset_protocol::AssetRoots;
+use attachments::{AttachmentIngestor, get_attachment_policy};
+use auth::{AuthManager, generate_auth_session, handle_auth_callback, clear_auth_session, clear_all_auth_sessions, get_auth_session, sweep_expired_auth_sessions, sign_out_everywhere, factory_reset_auth_store, rotate_auth_store_key, exchange_auth_code, refresh_auth_token};
+use auth_recovery::{RecoveryState, get_auth_store_recovery};
+use backend::BackendClient;
+use blob_store::{BlobStore, put_blob, add_blob_ref, release_blob_ref, release_conversation_blobs, collect_blob_garbage, get_blob_stats};
+use deep_link::{DeepLinkInbox, setup_deep_linking, open_auth_url, register_auth_protocol, get_current_deep_link, deep_link_ready, ack_deep_link, drain_deep_links};
+use deep_link_policy::DeepLinkPolicy;
+use device::get_device_identity;
+use dpop::get_device_key_thumbprint;
+use error::get_error_catalogue;
+use external_links::{ExternalLinks, open_external, check_external_link, get_external_link_policy};
+use files::{FileManager, open_file_dialog, save_file_dialog, read_file_chunk, write_file_chunk, commit_file_handle, close_file_handle, save_artifact};
+use jwt::{JwtConfig, JwtValidator, verify_access_token};
+use migrations::{MigrationState, get_migration_report, plan_store_migrations};
+use navigation::NavigationGuard;
+use remote_sessions::{list_remote_sessions, revoke_remote_session, revoke_other_sessions};
+use share::{ShareInbox, confirm_share_import, reject_share_import};
+use vault::{Vault, get_vault_status, lock_vault, unlock_vault};
 
 #[cfg(target_os = "linux")]
 use std::process::Command;
@@ -54,22 +98,170 @@ pub fn run() {
     .plugin(tauri_plugin_deep_link::init())
     .plugin(tauri_plugin_store::Builder::default().build())
     .plugin(tauri_plugin_opener::init())
-    .invoke_handler(tauri::generate_handler![
+    .plugin(tauri_plugin_dialog::init())
+    .register_asynchronous_uri_scheme_protocol(blob_store::BLOB_SCHEME, blob_store::handle_blob_protocol)
+    .register_asynchronous_uri_scheme_protocol(asset_protocol::ASSET_SCHEME, asset_protocol::handle_asset_protocol)
+    // Every call is checked against the caller's window, origin and vault state first
+    .invoke_handler(ipc_guard::guarded(tauri::generate_handler![
       generate_auth_session,
       handle_auth_callback,
       clear_auth_session,
       clear_all_auth_sessions,
       get_auth_session,
+      sweep_expired_auth_sessions,
+      sign_out_everywhere,
+      factory_reset_auth_store,
+      rotate_auth_store_key,
+      exchange_auth_code,
+      refresh_auth_token,
+      get_auth_store_recovery,
       open_auth_url,
+      open_external,
+      check_external_link,
+      get_external_link_policy,
       register_auth_protocol,
-      get_current_deep_link
-    ])
+      get_current_deep_link,
+      deep_link_ready,
+      ack_deep_link,
+      drain_deep_links,
+      confirm_share_import,
+      reject_share_import,
+      open_file_dialog,
+      save_file_dialog,
+      read_file_chunk,
+      write_file_chunk,
+      commit_file_handle,
+      close_file_handle,
+      save_artifact,
+      get_attachment_policy,
+      put_blob,
+      add_blob_ref,
+      release_blob_ref,
+      release_conversation_blobs,
+      collect_blob_garbage,
+      get_blob_stats,
+      get_error_catalogue,
+      get_device_identity,
+      get_device_key_thumbprint,
+      verify_access_token,
+      get_migration_report,
+      plan_store_migrations,
+      get_vault_status,
+      lock_vault,
+      unlock_vault,
+      list_remote_sessions,
+      revoke_remote_session,
+      revoke_other_sessions,
+      get_background_activity
+    ]))
     .setup(|app| {
-      // Initialize auth manager
-      let auth_manager = AuthManager::new(app.handle()).expect("Failed to initialize auth manager");
+      // Refuse to start if the shipped config no longer matches the reviewed security profile
+      security::verify_security_profile(app.handle())?;
+      
+      // A corrupt auth store is moved aside so the app starts signed out instead of crashing;
+      // one that cannot be read or moved stops startup rather than being overwritten
+      let recovery = auth_recovery::check_auth_store(app.handle())?;
+      
+      // Bring persisted stores up to the current schema before anything loads them
+      let migration_reports = migrations::run_startup_migrations(app.handle());
+      app.manage(MigrationState::new(migration_reports));
+      
+      // Initialize auth manager, device identity and device key; a store that fails to load is quarantined too
+      let (auth_manager, device_manager, device_key, load_recovery) = auth_recovery::load_or_recover(app.handle())?;
+      let recovery = load_recovery.or(recovery);
+      app.manage(RecoveryState::new(recovery.clone()));
+      // A stored session stays behind the lock until the user unlocks or signs in again
+      let vault = if auth_manager.has_stored_session() { Vault::locked() } else { Vault::new() };
       app.manage(auth_manager);
+      app.manage(device_manager);
+      app.manage(device_key);
+      app.manage(JwtValidator::new(JwtConfig::from_env()));
+      app.manage(BackendClient::from_env());
+      app.manage(ExternalLinks::from_env());
+      app.manage(NavigationGuard::new(app.handle()));
+      app.manage(vault);
+      app.manage(BackgroundActivity::new());
+      app.manage(FileManager::new());
+      app.manage(AttachmentIngestor::new());
+      let blob_store = BlobStore::new(app.handle()).expect("Failed to initialize blob store");
+      app.manage(blob_store);
+      let asset_roots = AssetRoots::new(app.handle()).expect("Failed to initialize asset roots");
+      app.manage(asset_roots);
+      
+      // Purge abandoned sign-in attempts once their session or PKCE challenge expires
+      let sweep_handle = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5 * 60));
+        loop {
+          interval.tick().await;
+          match sweep_handle.state::<AuthManager>().sweep_expired() {
+            Ok(stats) if stats.purged() > 0 => log::info!("Auth sweep: {:?}", stats),
+            Ok(_) => {}
+            Err(e) => log::error!("Auth sweep failed: {}", e),
+          }
+        }
+      });
+      
+      // Notice when this device's session is revoked from another device
+      let revocation_handle = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5 * 60));
+        loop {
+          interval.tick().await;
+          revocation_handle.state::<BackgroundActivity>().wait_until_active().await;
+          if let Err(e) = remote_sessions::check_current_session(&revocation_handle).await {
+            log::warn!("Session revocation check failed: {}", e);
+          }
+        }
+      });
+      
+      // Rotate the auth store key once it reaches its maximum age
+      let rotation_handle = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(24 * 60 * 60));
+        loop {
+          interval.tick().await;
+          rotation_handle.state::<BackgroundActivity>().wait_until_active().await;
+          let auth_manager = rotation_handle.state::<AuthManager>();
+          if auth_manager.store_key_age() < auth::STORE_KEY_MAX_AGE {
+            continue;
+          }
+          if let Err(e) = auth_manager.rotate_store_key("scheduled") {
+            log::error!("Auth store key rotation failed: {}", e);
+          }
+        }
+      });
+      
+      // Periodically drop orphaned blobs and trim the store back under quota
+      let gc_handle = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+        loop {
+          interval.tick().await;
+          gc_handle.state::<BackgroundActivity>().wait_until_active().await;
+          match gc_handle.state::<BlobStore>().collect_garbage() {
+            Ok(stats) => log::info!("Blob GC: {:?}", stats),
+            Err(e) => log::error!("Blob GC failed: {}", e),
+          }
+        }
+      });
+      
+      // Lock the vault and pause network jobs while the session is locked, asleep or idle
+      #[cfg(target_os = "linux")]
+      {
+        let logind_handle = app.handle().clone();
+        tauri::async_runtime::spawn(async move {
+          match logind::watch(logind_handle).await {
+            Ok(()) => log::warn!("logind signal stream ended"),
+            Err(e) => log::warn!("logind is unavailable, lock and sleep handling disabled: {}", e),
+          }
+        });
+      }
       
       // Setup deep linking
+      app.manage(DeepLinkPolicy::new());
+      app.manage(DeepLinkInbox::new());
+      app.manage(ShareInbox::from_env());
       let app_handle = app.handle().clone();
       tauri::async_runtime::spawn(async move {
         if let Err(e) = setup_deep_linking(&app_handle).await {
@@ -77,38 +269,18 @@ pub fn run() {
         }
       });
       
-      // Create and set menu on the main window
-      let main_window = app.get_webview_window("main").unwrap();
+      // The main window keeps navigation on the app origin; other links go to the system browser
+      let main_window = navigation::build_main_window(app.handle())?;
       
-      // Store app handle for use in event handler
-      let app_handle = app.handle().clone();
+      // Reported once there is a window to show it; late listeners ask get_auth_store_recovery
+      if let Some(report) = &recovery {
+        auth_recovery::notify_recovery(app.handle(), report);
+      }
       
-      // Handle deep link events (symlog:// protocol)
-      app.listen("deep-link", move |event| {
-        let payload = event.payload();
-        println!("Received deep link: {}", payload);
-        // The payload should contain the symlog:// URL
-        if let Ok(url) = serde_json::from_str::<String>(payload) {
-          if url.starts_with("symlog://auth") {
-            // Extract auth code from URL
-            if let Some(code_start) = url.find("code=") {
-              let code = &url[code_start + 5..];
-              // Remove any additional query parameters
-              let auth_code = if let Some(amp_pos) = code.find('&') {
-                &code[..amp_pos]
-              } else {
-                code
-              };
-              
-              println!("Extracted auth code: {}", auth_code);
-              
-              // Emit event to frontend with the auth code
-              if let Err(e) = app_handle.emit("auth-code-received", auth_code) {
-                eprintln!("Failed to emit auth code event: {}", e);
-              }
-            }
-          }
-        }
+      // Files dropped onto the window become chat attachments
+      let drop_handle = app.handle().clone();
+      main_window.on_window_event(move |event| {
+        attachments::handle_window_event(&drop_handle, event);
       });
       
       // Apply window effects for a futuristic look
@@ -0,0 +1,364 @@
+use futures_util::StreamExt;
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+use zbus::zvariant::OwnedObjectPath;
+use zbus::Connection;
+use crate::activity::{BackgroundActivity, PauseReason};
+use crate::auth::AuthManager;
+use crate::navigation::MAIN_WINDOW;
+use crate::remote_sessions;
+use crate::vault::Vault;
+
+// Overrides the system bus, e.g. a private bus running a mock logind
+const BUS_ADDRESS_VAR: &str = "SYMLOG_LOGIND_BUS_ADDRESS";
+// Set to 1 to blur the main window while the screen is locked
+const BLUR_ON_LOCK_VAR: &str = "SYMLOG_BLUR_ON_LOCK";
+
+pub const SYSTEM_LOCK_EVENT: &str = "system_lock_changed";
+
+const BLUR_SCRIPT: &str = "document.documentElement.style.filter = 'blur(16px)'";
+const UNBLUR_SCRIPT: &str = "document.documentElement.style.filter = ''";
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Login1Manager {
+    #[zbus(name = "GetSessionByPID")]
+    fn get_session_by_pid(&self, pid: u32) -> zbus::Result<OwnedObjectPath>;
+
+    fn get_session(&self, session_id: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(interface = "org.freedesktop.login1.Session", default_service = "org.freedesktop.login1")]
+trait Login1Session {
+    #[zbus(signal)]
+    fn lock(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn unlock(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn idle_hint(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SystemEvent {
+    Lock,
+    Unlock,
+    Sleep,
+    Wake,
+    Idle(bool),
+}
+
+fn blur_on_lock() -> bool {
+    std::env::var(BLUR_ON_LOCK_VAR).is_ok_and(|v| v == "1" || v == "true")
+}
+
+fn set_blur(app: &AppHandle, blurred: bool) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        if let Err(e) = window.eval(if blurred { BLUR_SCRIPT } else { UNBLUR_SCRIPT }) {
+            log::error!("Failed to update lock blur: {}", e);
+        }
+    }
+}
+
+// Reports the vault as it is, so the webview never treats a still locked vault as open
+fn notify(app: &AppHandle) {
+    let locked = app.state::<Vault>().is_locked();
+    if let Err(e) = app.emit(SYSTEM_LOCK_EVENT, json!({ "locked": locked })) {
+        log::error!("Failed to emit system lock event: {}", e);
+    }
+}
+
+// Locking forgets derived keys; they are derived again once the vault is restored
+fn lock_vault(app: &AppHandle) {
+    if app.state::<Vault>().lock_for_system(&app.state::<AuthManager>()) {
+        log::info!("Vault locked by the system");
+    }
+}
+
+// Only once the screen is unlocked and the machine awake. The session may have been
+// revoked while we were away, so it is checked straight away rather than on the next tick.
+fn restore_vault(app: &AppHandle, activity: &BackgroundActivity) {
+    let paused_by = activity.paused_by();
+    if paused_by.contains(&PauseReason::ScreenLocked) || paused_by.contains(&PauseReason::Sleeping) {
+        return;
+    }
+    if !app.state::<Vault>().resume_after_system_lock() {
+        return;
+    }
+    log::info!("Vault restored after the system lock");
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = remote_sessions::check_current_session(&app).await {
+            log::warn!("Session revocation check after resume failed: {}", e);
+        }
+    });
+}
+
+fn handle(app: &AppHandle, event: SystemEvent) {
+    let activity = app.state::<BackgroundActivity>();
+    log::info!("logind: {:?}", event);
+    match event {
+        SystemEvent::Lock => {
+            lock_vault(app);
+            activity.pause(PauseReason::ScreenLocked);
+            if blur_on_lock() {
+                set_blur(app, true);
+            }
+        }
+        SystemEvent::Unlock => {
+            activity.resume(PauseReason::ScreenLocked);
+            set_blur(app, false);
+            restore_vault(app, &activity);
+        }
+        SystemEvent::Sleep => {
+            lock_vault(app);
+            activity.pause(PauseReason::Sleeping);
+        }
+        SystemEvent::Wake => {
+            activity.resume(PauseReason::Sleeping);
+            restore_vault(app, &activity);
+        }
+        SystemEvent::Idle(true) => {
+            activity.pause(PauseReason::Idle);
+            return;
+        }
+        SystemEvent::Idle(false) => {
+            activity.resume(PauseReason::Idle);
+            return;
+        }
+    }
+    notify(app);
+}
+
+async fn connect() -> zbus::Result<Connection> {
+    match std::env::var(BUS_ADDRESS_VAR) {
+        Ok(address) => zbus::connection::Builder::address(address.as_str())?.build().await,
+        Err(_) => Connection::system().await,
+    }
+}
+
+// Runs for the life of the app; returns only if logind is unreachable or the bus goes away
+pub async fn watch(app: AppHandle) -> zbus::Result<()> {
+    let connection = connect().await?;
+    listen(&connection, |event| handle(&app, event)).await
+}
+
+async fn listen(connection: &Connection, mut on_event: impl FnMut(SystemEvent)) -> zbus::Result<()> {
+    let manager = Login1ManagerProxy::new(connection).await?;
+    // Signals are emitted on the real session path, never on the "auto" alias
+    let session_path = match manager.get_session_by_pid(std::process::id()).await {
+        Ok(path) => path,
+        Err(_) => manager.get_session("auto").await?,
+    };
+    let session = Login1SessionProxy::builder(connection)
+        .path(session_path)?
+        .build()
+        .await?;
+
+    let mut sleep = manager.receive_prepare_for_sleep().await?;
+    let mut lock = session.receive_lock().await?;
+    let mut unlock = session.receive_unlock().await?;
+    let mut idle = session.receive_idle_hint_changed().await;
+
+    // The app may start on an already locked screen
+    if session.locked_hint().await.unwrap_or(false) {
+        on_event(SystemEvent::Lock);
+    }
+
+    loop {
+        let event = tokio::select! {
+            Some(signal) = sleep.next() => match signal.args() {
+                Ok(args) if *args.start() => SystemEvent::Sleep,
+                Ok(_) => SystemEvent::Wake,
+                Err(e) => {
+                    log::warn!("Malformed PrepareForSleep signal: {}", e);
+                    continue;
+                }
+            },
+            Some(_) = lock.next() => SystemEvent::Lock,
+            Some(_) = unlock.next() => SystemEvent::Unlock,
+            Some(change) = idle.next() => match change.get().await {
+                Ok(idle) => SystemEvent::Idle(idle),
+                Err(e) => {
+                    log::warn!("Failed to read IdleHint: {}", e);
+                    continue;
+                }
+            },
+            else => return Ok(()),
+        };
+        on_event(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use zbus::object_server::SignalEmitter;
+    use super::*;
+
+    const MANAGER_PATH: &str = "/org/freedesktop/login1";
+    const SESSION_PATH: &str = "/org/freedesktop/login1/session/_31";
+
+    const BUS_CONFIG: &str = r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+     "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+    <busconfig>
+      <type>session</type>
+      <listen>unix:tmpdir=/tmp</listen>
+      <auth>EXTERNAL</auth>
+      <policy context="default">
+        <allow send_destination="*" eavesdrop="true"/>
+        <allow eavesdrop="true"/>
+        <allow own="*"/>
+      </policy>
+    </busconfig>"#;
+
+    struct MockManager;
+
+    #[zbus::interface(name = "org.freedesktop.login1.Manager")]
+    impl MockManager {
+        #[zbus(name = "GetSessionByPID")]
+        fn get_session_by_pid(&self, _pid: u32) -> zbus::fdo::Result<OwnedObjectPath> {
+            Err(zbus::fdo::Error::Failed("PID does not belong to any known session".to_string()))
+        }
+
+        fn get_session(&self, _session_id: &str) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(SESSION_PATH).unwrap()
+        }
+
+        #[zbus(signal)]
+        async fn prepare_for_sleep(emitter: &SignalEmitter<'_>, start: bool) -> zbus::Result<()>;
+    }
+
+    struct MockSession {
+        idle: bool,
+        locked: bool,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.login1.Session")]
+    impl MockSession {
+        #[zbus(signal)]
+        async fn lock(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        async fn unlock(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+        #[zbus(property)]
+        fn idle_hint(&self) -> bool {
+            self.idle
+        }
+
+        #[zbus(property)]
+        fn locked_hint(&self) -> bool {
+            self.locked
+        }
+    }
+
+    // A dbus-daemon of our own, so the test never touches the real system bus
+    struct PrivateBus(Child);
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn start_bus() -> Option<(PrivateBus, String)> {
+        let config = std::env::temp_dir().join(format!("symlog-logind-{}.conf", uuid::Uuid::new_v4()));
+        std::fs::write(&config, BUS_CONFIG).unwrap();
+        let mut daemon = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+        // Read once the daemon prints its address
+        let _ = std::fs::remove_file(&config);
+        Some((PrivateBus(daemon), address.trim().to_string()))
+    }
+
+    async fn next(events: &mut mpsc::UnboundedReceiver<SystemEvent>) -> SystemEvent {
+        tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("no logind event within 5s")
+            .expect("listener stopped")
+    }
+
+    #[tokio::test]
+    async fn translates_mock_logind_signals() {
+        let Some((_bus, address)) = start_bus() else {
+            eprintln!("dbus-daemon is not installed, skipping");
+            return;
+        };
+        let logind = zbus::connection::Builder::address(address.as_str())
+            .unwrap()
+            .name("org.freedesktop.login1")
+            .unwrap()
+            .serve_at(MANAGER_PATH, MockManager)
+            .unwrap()
+            .serve_at(SESSION_PATH, MockSession { idle: false, locked: true })
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let manager = logind
+            .object_server()
+            .interface::<_, MockManager>(MANAGER_PATH)
+            .await
+            .unwrap();
+        let session = logind
+            .object_server()
+            .interface::<_, MockSession>(SESSION_PATH)
+            .await
+            .unwrap();
+
+        std::env::set_var(BUS_ADDRESS_VAR, &address);
+        let connection = connect().await.unwrap();
+        let (sender, mut events) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            listen(&connection, |event| {
+                let _ = sender.send(event);
+            })
+            .await
+        });
+
+        // Started on a locked screen; the idle stream opens with the current hint
+        assert_eq!(next(&mut events).await, SystemEvent::Lock);
+        assert_eq!(next(&mut events).await, SystemEvent::Idle(false));
+
+        MockSession::unlock(session.signal_emitter()).await.unwrap();
+        assert_eq!(next(&mut events).await, SystemEvent::Unlock);
+
+        MockManager::prepare_for_sleep(manager.signal_emitter(), true).await.unwrap();
+        assert_eq!(next(&mut events).await, SystemEvent::Sleep);
+        MockManager::prepare_for_sleep(manager.signal_emitter(), false).await.unwrap();
+        assert_eq!(next(&mut events).await, SystemEvent::Wake);
+
+        MockSession::lock(session.signal_emitter()).await.unwrap();
+        assert_eq!(next(&mut events).await, SystemEvent::Lock);
+
+        session.get_mut().await.idle = true;
+        session
+            .get()
+            .await
+            .idle_hint_changed(session.signal_emitter())
+            .await
+            .unwrap();
+        assert_eq!(next(&mut events).await, SystemEvent::Idle(true));
+    }
+}
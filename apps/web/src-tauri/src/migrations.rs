@@ -0,0 +1,416 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{command, AppHandle, State};
+use tauri_plugin_store::resolve_store_path;
+use chrono::{DateTime, Utc};
+use crate::auth::{StoreNamespace, AUTH_STORE};
+use crate::blob_store::BLOB_INDEX_STORE;
+use crate::error::{CommandError, ErrorCode};
+use crate::files::write_atomic;
+
+// Reserved in every shell store; absent means the store predates versioning (v0)
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+const DRY_RUN_VAR: &str = "SYMLOG_MIGRATIONS_DRY_RUN";
+
+type MigrateFn = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+// Upgrades a store from `from` to `from + 1`
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: MigrateFn,
+}
+
+struct StoreSchema {
+    file: &'static str,
+    migrations: &'static [Migration],
+}
+
+impl StoreSchema {
+    fn current_version(&self) -> u32 {
+        self.migrations.last().map(|m| m.from + 1).unwrap_or(0)
+    }
+}
+
+// Append-only: a shipped migration is never edited or reordered
+const SCHEMAS: &[StoreSchema] = &[
+    StoreSchema {
+        file: AUTH_STORE,
+        migrations: &[Migration {
+            from: 0,
+            description: "Wrap bare encrypted session and secret strings in envelopes",
+            apply: auth_v0_wrap_envelopes,
+        }],
+    },
+    StoreSchema {
+        file: BLOB_INDEX_STORE,
+        migrations: &[Migration {
+            from: 0,
+            description: "Record the baseline blob index schema",
+            apply: baseline,
+        }],
+    },
+];
+
+fn auth_v0_wrap_envelopes(entries: &mut Map<String, Value>) -> Result<(), String> {
+    for (key, value) in entries.iter_mut() {
+        if !matches!(
+            StoreNamespace::of(key),
+            Some(StoreNamespace::Session | StoreNamespace::Secret)
+        ) {
+            continue;
+        }
+        match value {
+            Value::String(payload) => *value = json!({ "key_version": 0, "payload": payload }),
+            Value::Object(_) => {}
+            _ => return Err(format!("{} is neither an envelope nor an encrypted string", key)),
+        }
+    }
+    Ok(())
+}
+
+fn baseline(_entries: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub store: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub applied: Vec<String>,
+    pub dry_run: bool,
+    pub backup_path: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+// Reports from the startup run
+pub struct MigrationState {
+    reports: Vec<MigrationReport>,
+}
+
+impl MigrationState {
+    pub fn new(reports: Vec<MigrationReport>) -> Self {
+        Self { reports }
+    }
+}
+
+fn stored_version(entries: &Map<String, Value>) -> Result<u32, String> {
+    match entries.get(SCHEMA_VERSION_KEY) {
+        None => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("{} is not a version number", SCHEMA_VERSION_KEY)),
+    }
+}
+
+fn backup(path: &Path, version: u32) -> Result<PathBuf, String> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let backup_path = path.with_file_name(format!(
+        "{}.v{}-{}.bak",
+        file_name,
+        version,
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    fs::copy(path, &backup_path).map_err(|e| format!("backup failed: {}", e))?;
+    Ok(backup_path)
+}
+
+// Runs pending migrations in memory, then backs up and atomically replaces the file.
+// A failed migration leaves the file exactly as it was.
+fn migrate_file(path: &Path, schema: &StoreSchema, dry_run: bool) -> Option<MigrationReport> {
+    let target = schema.current_version();
+    let mut report = MigrationReport {
+        store: schema.file.to_string(),
+        from_version: 0,
+        to_version: target,
+        applied: Vec::new(),
+        dry_run,
+        backup_path: None,
+        error: None,
+    };
+
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        // A fresh install is stamped with the current version on first save
+        Err(e) if e.kind() == ErrorKind::NotFound => return None,
+        Err(e) => {
+            report.error = Some(e.to_string());
+            return Some(report);
+        }
+    };
+    let mut entries: Map<String, Value> = match serde_json::from_slice(&bytes) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report.error = Some(format!("store is not a JSON object: {}", e));
+            return Some(report);
+        }
+    };
+
+    let version = match stored_version(&entries) {
+        Ok(version) => version,
+        Err(e) => {
+            report.error = Some(e);
+            return Some(report);
+        }
+    };
+    report.from_version = version;
+    if version == target {
+        return None;
+    }
+    if version > target {
+        // Written by a newer build; touching it could lose fields this build does not know
+        report.error = Some(format!("store is at v{}, newer than supported v{}", version, target));
+        return Some(report);
+    }
+
+    for migration in schema.migrations.iter().filter(|m| m.from >= version) {
+        if let Err(e) = (migration.apply)(&mut entries) {
+            report.error = Some(format!("v{} -> v{} failed: {}", migration.from, migration.from + 1, e));
+            return Some(report);
+        }
+        report.applied.push(migration.description.to_string());
+    }
+    entries.insert(SCHEMA_VERSION_KEY.to_string(), json!(target));
+
+    if dry_run {
+        return Some(report);
+    }
+
+    let result = backup(path, version).and_then(|backup_path| {
+        report.backup_path = Some(backup_path);
+        let bytes = serde_json::to_vec_pretty(&entries).map_err(|e| e.to_string())?;
+        write_atomic(path, &bytes).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        report.error = Some(e);
+    }
+    Some(report)
+}
+
+fn run(app: &AppHandle, dry_run: bool) -> Vec<MigrationReport> {
+    let mut reports = Vec::new();
+    for schema in SCHEMAS {
+        let path = match resolve_store_path(app, schema.file) {
+            Ok(path) => path,
+            Err(e) => {
+                log::error!("Cannot resolve {} for migration: {}", schema.file, e);
+                continue;
+            }
+        };
+        if let Some(report) = migrate_file(&path, schema, dry_run) {
+            match &report.error {
+                Some(e) => log::error!("Migrating {} failed: {}", report.store, e),
+                None => log::info!(
+                    "{} {} v{} -> v{}: {:?}",
+                    if dry_run { "Planned migration of" } else { "Migrated" },
+                    report.store,
+                    report.from_version,
+                    report.to_version,
+                    report.applied
+                ),
+            }
+            reports.push(report);
+        }
+    }
+    reports
+}
+
+// Must run before any store is first loaded by the store plugin
+pub fn run_startup_migrations(app: &AppHandle) -> Vec<MigrationReport> {
+    let dry_run = std::env::var(DRY_RUN_VAR).is_ok_and(|v| v == "1" || v == "true");
+    run(app, dry_run)
+}
+
+// Newly created stores carry the current version so they are never migrated from v0
+pub fn current_version(file: &str) -> u32 {
+    SCHEMAS
+        .iter()
+        .find(|schema| schema.file == file)
+        .map(StoreSchema::current_version)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    pub reports: Vec<MigrationReport>,
+    pub checked_at: DateTime<Utc>,
+}
+
+// Tauri commands
+#[command]
+pub async fn get_migration_report(
+    migrations: State<'_, MigrationState>,
+) -> Result<Vec<MigrationReport>, CommandError> {
+    Ok(migrations.reports.clone())
+}
+
+#[command]
+pub async fn plan_store_migrations(app: AppHandle) -> Result<MigrationStatus, CommandError> {
+    let reports = tauri::async_runtime::spawn_blocking(move || run(&app, true))
+        .await
+        .map_err(|e| CommandError::new(ErrorCode::Internal, e.to_string()))?;
+    Ok(MigrationStatus {
+        reports,
+        checked_at: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION_PAYLOAD: &str = "c2VhbGVkIHNlc3Npb24=";
+    const SECRET_PAYLOAD: &str = "c2VhbGVkIHNlY3JldA==";
+
+    // Each test gets its own directory so backups from one never show up in another
+    fn fixture(file: &str, contents: &Value) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("symlog-migrations-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(file);
+        fs::write(&path, serde_json::to_vec_pretty(contents).unwrap()).unwrap();
+        path
+    }
+
+    fn schema(file: &str) -> &'static StoreSchema {
+        SCHEMAS.iter().find(|schema| schema.file == file).unwrap()
+    }
+
+    fn backups(path: &Path) -> Vec<PathBuf> {
+        fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|entry| entry.to_string_lossy().ends_with(".bak"))
+            .collect()
+    }
+
+    fn read(path: &Path) -> Map<String, Value> {
+        serde_json::from_slice(&fs::read(path).unwrap()).unwrap()
+    }
+
+    // auth.json as written before envelopes and schema versions existed
+    fn auth_v0() -> Value {
+        json!({
+            "key_derivation_salt": "c29tZXNhbHRzb21lc2FsdA",
+            "session_5f1c": SESSION_PAYLOAD,
+            "secret_dpop_device_key": SECRET_PAYLOAD,
+            "device_identity": { "device_id": "d-1" },
+        })
+    }
+
+    fn blobs_v0() -> Value {
+        json!({
+            "blob_ab12": {
+                "hash": "ab12",
+                "size": 3,
+                "mime_type": "text/plain",
+                "created_at": "2025-01-01T00:00:00Z",
+                "last_accessed": "2025-01-01T00:00:00Z",
+                "refs": ["conversation-1"],
+            },
+        })
+    }
+
+    #[test]
+    fn auth_v0_is_wrapped_backed_up_and_stamped() {
+        let path = fixture(AUTH_STORE, &auth_v0());
+        let original = fs::read(&path).unwrap();
+
+        let report = migrate_file(&path, schema(AUTH_STORE), false).unwrap();
+        assert_eq!(report.error, None);
+        assert_eq!((report.from_version, report.to_version), (0, 1));
+        assert_eq!(report.applied.len(), 1);
+
+        let backup_path = report.backup_path.unwrap();
+        assert_eq!(backups(&path), vec![backup_path.clone()]);
+        assert_eq!(fs::read(&backup_path).unwrap(), original);
+
+        let entries = read(&path);
+        assert_eq!(entries[SCHEMA_VERSION_KEY], json!(1));
+        assert_eq!(entries["session_5f1c"], json!({ "key_version": 0, "payload": SESSION_PAYLOAD }));
+        assert_eq!(
+            entries["secret_dpop_device_key"],
+            json!({ "key_version": 0, "payload": SECRET_PAYLOAD })
+        );
+        assert_eq!(entries["device_identity"], auth_v0()["device_identity"]);
+    }
+
+    #[test]
+    fn blobs_v0_is_backed_up_and_stamped() {
+        let path = fixture(BLOB_INDEX_STORE, &blobs_v0());
+
+        let report = migrate_file(&path, schema(BLOB_INDEX_STORE), false).unwrap();
+        assert_eq!(report.error, None);
+        assert!(report.backup_path.is_some_and(|backup_path| backup_path.exists()));
+
+        let entries = read(&path);
+        assert_eq!(entries[SCHEMA_VERSION_KEY], json!(1));
+        assert_eq!(entries["blob_ab12"], blobs_v0()["blob_ab12"]);
+    }
+
+    #[test]
+    fn current_store_is_left_alone() {
+        let mut contents = blobs_v0();
+        contents[SCHEMA_VERSION_KEY] = json!(1);
+        let path = fixture(BLOB_INDEX_STORE, &contents);
+
+        assert!(migrate_file(&path, schema(BLOB_INDEX_STORE), false).is_none());
+        assert!(backups(&path).is_empty());
+    }
+
+    #[test]
+    fn dry_run_plans_without_writing() {
+        for (file, contents) in [(AUTH_STORE, auth_v0()), (BLOB_INDEX_STORE, blobs_v0())] {
+            let path = fixture(file, &contents);
+            let original = fs::read(&path).unwrap();
+
+            let report = migrate_file(&path, schema(file), true).unwrap();
+            assert!(report.dry_run);
+            assert_eq!(report.error, None);
+            assert_eq!(report.applied.len(), 1);
+            assert_eq!(report.backup_path, None);
+
+            assert_eq!(fs::read(&path).unwrap(), original);
+            assert!(backups(&path).is_empty());
+        }
+    }
+
+    #[test]
+    fn newer_schema_version_is_refused() {
+        let mut contents = auth_v0();
+        contents[SCHEMA_VERSION_KEY] = json!(7);
+        let path = fixture(AUTH_STORE, &contents);
+        let original = fs::read(&path).unwrap();
+
+        let report = migrate_file(&path, schema(AUTH_STORE), false).unwrap();
+        assert_eq!(report.from_version, 7);
+        assert!(report.error.unwrap().contains("newer than supported"));
+        assert!(report.applied.is_empty());
+
+        assert_eq!(fs::read(&path).unwrap(), original);
+        assert!(backups(&path).is_empty());
+    }
+
+    #[test]
+    fn failed_migration_leaves_file_byte_identical() {
+        let mut contents = auth_v0();
+        contents["session_broken"] = json!(42);
+        let path = fixture(AUTH_STORE, &contents);
+        let original = fs::read(&path).unwrap();
+
+        let report = migrate_file(&path, schema(AUTH_STORE), false).unwrap();
+        assert!(report.error.unwrap().contains("session_broken"));
+        assert!(report.applied.is_empty());
+        assert_eq!(report.backup_path, None);
+
+        assert_eq!(fs::read(&path).unwrap(), original);
+        assert!(backups(&path).is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use tauri::utils::config::WindowConfig;
+use tauri::webview::NewWindowResponse;
+use tauri::{AppHandle, Manager, WebviewWindow, WebviewWindowBuilder};
+use url::Url;
+use crate::deep_link_policy::redact;
+use crate::external_links::{ExternalLinks, LinkPurpose};
+
+pub const MAIN_WINDOW: &str = "main";
+
+// Where the bundled frontend is served from on each platform
+const BUNDLED_ORIGINS: &[&str] = &["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost"];
+
+// Scheme, host and port. Url::origin() is opaque for custom schemes such as tauri:// and
+// never compares equal, so origins are matched on their parts instead.
+type OriginParts = (String, String, Option<u16>);
+
+fn origin_parts(url: &Url) -> Option<OriginParts> {
+    Some((
+        url.scheme().to_string(),
+        url.host_str()?.to_ascii_lowercase(),
+        url.port_or_known_default(),
+    ))
+}
+
+pub struct NavigationGuard {
+    origins: Vec<OriginParts>,
+}
+
+impl NavigationGuard {
+    pub fn new(app: &AppHandle) -> Self {
+        let mut origins: Vec<OriginParts> = BUNDLED_ORIGINS
+            .iter()
+            .filter_map(|origin| Url::parse(origin).ok())
+            .filter_map(|url| origin_parts(&url))
+            .collect();
+        // The dev server is only part of the app in development builds
+        if cfg!(debug_assertions) {
+            if let Some(dev_url) = &app.config().build.dev_url {
+                origins.extend(origin_parts(dev_url));
+            }
+        }
+        Self { origins }
+    }
+
+    pub fn is_app_origin(&self, url: &Url) -> bool {
+        origin_parts(url).is_some_and(|parts| self.origins.contains(&parts))
+    }
+}
+
+// Off-origin web links leave the app through the external link policy; anything else
+// (file:, data:, javascript:, unknown schemes) is dropped
+fn route_external(app: &AppHandle, url: &Url) {
+    if !matches!(url.scheme(), "https" | "http") {
+        return;
+    }
+    let app = app.clone();
+    let url = url.to_string();
+    tauri::async_runtime::spawn(async move {
+        let links = app.state::<ExternalLinks>();
+        if let Err(e) = links.open(&app, &url, LinkPurpose::Link).await {
+            log::warn!("External link from the webview was not opened: {}", e);
+        }
+    });
+}
+
+// Builds the main window from its tauri.conf.json entry (which sets `create: false`)
+// so the navigation hooks are installed before the first page loads. Expects
+// NavigationGuard to be managed already.
+pub fn build_main_window(app: &AppHandle) -> tauri::Result<WebviewWindow> {
+    let config: WindowConfig = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|window| window.label == MAIN_WINDOW)
+        .cloned()
+        .unwrap_or_default();
+    let navigation_app = app.clone();
+    let new_window_app = app.clone();
+
+    WebviewWindowBuilder::from_config(app, &config)?
+        .on_navigation(move |url| {
+            if navigation_app.state::<NavigationGuard>().is_app_origin(url) {
+                return true;
+            }
+            log::warn!("Blocked navigation of the main window to {}", redact(url.as_str()));
+            route_external(&navigation_app, url);
+            false
+        })
+        .on_new_window(move |url, _features| {
+            // In-app pages replace the current view instead of spawning a second webview
+            // that would share the main window's capabilities
+            if new_window_app.state::<NavigationGuard>().is_app_origin(&url) {
+                let app = new_window_app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                        if let Err(e) = window.navigate(url) {
+                            log::error!("Failed to open in-app page: {}", e);
+                        }
+                    }
+                });
+            } else {
+                log::warn!("Blocked window.open to {}", redact(url.as_str()));
+                route_external(&new_window_app, &url);
+            }
+            NewWindowResponse::Deny
+        })
+        .build()
+}
@@ -0,0 +1,398 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use chrono::{DateTime, TimeZone, Utc};
+use crate::auth::{AuthError, AuthManager};
+use crate::backend::BackendClient;
+use crate::dpop::DeviceKey;
+use crate::error::CommandError;
+use crate::vault::Vault;
+
+pub const SESSION_REVOKED_EVENT: &str = "session_revoked";
+const REVOKE_REASON: &str = "revoked_from_desktop";
+
+// The subset of a `sessions` document the shell exposes; the document also carries the
+// session's access and refresh tokens, which are deliberately not deserialized
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionDocument {
+    #[serde(rename = "_id")]
+    id: String,
+    device_id: String,
+    device_name: Option<String>,
+    device_type: String,
+    platform: String,
+    user_agent: Option<String>,
+    ip_address: String,
+    created_at: f64,
+    last_activity_at: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteSession {
+    pub id: String,
+    pub device_id: String,
+    pub device_name: Option<String>,
+    pub device_type: String,
+    pub platform: String,
+    pub user_agent: Option<String>,
+    pub ip_address: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_activity_at: Option<DateTime<Utc>>,
+    // The session the caller's access token belongs to
+    pub is_current: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokeSummary {
+    pub revoked: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenStatus {
+    valid: bool,
+    reason: Option<String>,
+    user_id: Option<String>,
+    session_id: Option<String>,
+}
+
+fn millis(value: f64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(value as i64).single()
+}
+
+async fn token_status(
+    backend: &BackendClient,
+    access_token: &str,
+    device_key: &DeviceKey,
+) -> Result<TokenStatus, AuthError> {
+    backend
+        .query(
+            "sessions:validateAccessToken",
+            json!({ "accessToken": access_token }),
+            access_token,
+            device_key,
+        )
+        .await
+}
+
+// The backend resolves the access token to its account and session in one call. Other
+// installs can share this device id (a restored profile, a second OS user), so the
+// current session is the one the token belongs to, not the one with this device id
+async fn list(
+    access_token: &str,
+    device_key: &DeviceKey,
+    backend: &BackendClient,
+) -> Result<Vec<RemoteSession>, AuthError> {
+    let status = token_status(backend, access_token, device_key).await?;
+    let (user_id, current_id) = match status {
+        TokenStatus { valid: true, user_id: Some(user_id), session_id: Some(id), .. } => (user_id, id),
+        TokenStatus { reason, .. } => {
+            return Err(AuthError::InvalidToken(format!(
+                "access token has no active session ({})",
+                reason.as_deref().unwrap_or("unknown")
+            )))
+        }
+    };
+    let documents: Vec<SessionDocument> = backend
+        .query("sessions:getUserSessions", json!({ "userId": user_id }), access_token, device_key)
+        .await?;
+
+    Ok(documents
+        .into_iter()
+        .map(|doc| RemoteSession {
+            is_current: doc.id == current_id,
+            id: doc.id,
+            device_id: doc.device_id,
+            device_name: doc.device_name,
+            device_type: doc.device_type,
+            platform: doc.platform,
+            user_agent: doc.user_agent,
+            ip_address: doc.ip_address,
+            created_at: millis(doc.created_at),
+            last_activity_at: millis(doc.last_activity_at),
+        })
+        .collect())
+}
+
+// Only sessions of the signed-in account can be revoked; `sessions:revokeSession` does
+// not check ownership itself, so the id is matched against the account's list first
+fn revocable<'a>(sessions: &'a [RemoteSession], session_id: &str) -> Result<&'a RemoteSession, AuthError> {
+    let session = sessions
+        .iter()
+        .find(|session| session.id == session_id)
+        .ok_or_else(|| AuthError::UnknownSession(session_id.to_string()))?;
+    if session.is_current {
+        return Err(AuthError::CurrentSession);
+    }
+    Ok(session)
+}
+
+async fn revoke(
+    backend: &BackendClient,
+    session_id: &str,
+    access_token: &str,
+    device_key: &DeviceKey,
+) -> Result<(), AuthError> {
+    let _: serde_json::Value = backend
+        .mutation(
+            "sessions:revokeSession",
+            json!({ "sessionId": session_id, "reason": REVOKE_REASON }),
+            access_token,
+            device_key,
+        )
+        .await?;
+    Ok(())
+}
+
+async fn revoke_remote(
+    session_id: &str,
+    access_token: &str,
+    device_key: &DeviceKey,
+    backend: &BackendClient,
+) -> Result<(), AuthError> {
+    let sessions = list(access_token, device_key, backend).await?;
+    let session = revocable(&sessions, session_id)?;
+
+    revoke(backend, session_id, access_token, device_key).await?;
+    log::info!("Revoked remote session on {}", session.platform);
+    Ok(())
+}
+
+// Same local cleanup as signing out, then tells the webview why
+fn handle_revoked(app: &AppHandle) {
+    let auth_manager = app.state::<AuthManager>();
+    if let Err(e) = auth_manager.clear_all_sessions() {
+        log::error!("Failed to clear local sessions after remote revocation: {}", e);
+    }
+    auth_manager.forget_keys();
+    app.state::<Vault>().end_session();
+    log::warn!("Current session was revoked remotely; local sessions cleared");
+    if let Err(e) = app.emit(SESSION_REVOKED_EVENT, json!({ "reason": "revoked_remotely" })) {
+        log::error!("Failed to emit session revoked event: {}", e);
+    }
+}
+
+// Only an explicitly inactive session counts as revoked: expired or unknown tokens are
+// normal after a refresh and are left to the refresh flow
+async fn is_revoked(backend: &BackendClient, access_token: &str, device_key: &DeviceKey) -> Result<bool, AuthError> {
+    let status = token_status(backend, access_token, device_key).await?;
+    Ok(!status.valid && status.reason.as_deref() == Some("session_inactive"))
+}
+
+// Polled from setup
+pub async fn check_current_session(app: &AppHandle) -> Result<(), AuthError> {
+    let Some(access_token) = app.state::<Vault>().access_token() else {
+        return Ok(());
+    };
+    if is_revoked(&app.state::<BackendClient>(), &access_token, &app.state::<DeviceKey>()).await? {
+        handle_revoked(app);
+    }
+    Ok(())
+}
+
+// Tauri commands
+#[command]
+pub async fn list_remote_sessions(
+    access_token: String,
+    device_key: State<'_, DeviceKey>,
+    backend: State<'_, BackendClient>,
+) -> Result<Vec<RemoteSession>, CommandError> {
+    list(&access_token, &device_key, &backend)
+        .await
+        .map_err(CommandError::from)
+}
+
+#[command]
+pub async fn revoke_remote_session(
+    session_id: String,
+    access_token: String,
+    device_key: State<'_, DeviceKey>,
+    backend: State<'_, BackendClient>,
+) -> Result<(), CommandError> {
+    revoke_remote(&session_id, &access_token, &device_key, &backend)
+        .await
+        .map_err(CommandError::from)
+}
+
+#[command]
+pub async fn revoke_other_sessions(
+    access_token: String,
+    device_key: State<'_, DeviceKey>,
+    backend: State<'_, BackendClient>,
+) -> Result<RevokeSummary, CommandError> {
+    let sessions = list(&access_token, &device_key, &backend).await?;
+
+    let mut summary = RevokeSummary {
+        revoked: Vec::new(),
+        failed: Vec::new(),
+    };
+    for session in sessions.into_iter().filter(|session| !session.is_current) {
+        match revoke(&backend, &session.id, &access_token, &device_key).await {
+            Ok(()) => summary.revoked.push(session.id),
+            Err(e) => {
+                log::error!("Failed to revoke session {}: {}", session.id, e);
+                summary.failed.push(session.id);
+            }
+        }
+    }
+    log::info!(
+        "Revoked {} other sessions, {} failed",
+        summary.revoked.len(),
+        summary.failed.len()
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+    use serde_json::{json, Value};
+    use crate::backend::stand_in::{self, Recorded, Reply};
+    use super::*;
+
+    const ACCESS_TOKEN: &str = "header.payload.signature";
+    const USER_ID: &str = "user_1";
+    // Shared by this install and a second profile on the same machine
+    const DEVICE_ID: &str = "device-shared";
+
+    fn device_key() -> DeviceKey {
+        DeviceKey::from_signing_key(SigningKey::from_bytes(&[7u8; 32]))
+    }
+
+    fn document(id: &str, device_id: &str) -> Value {
+        json!({
+            "_id": id,
+            "deviceId": device_id,
+            "deviceName": null,
+            "deviceType": "desktop",
+            "platform": "linux",
+            "userAgent": null,
+            "ipAddress": "127.0.0.1",
+            "createdAt": 1_700_000_000_000.0,
+            "lastActivityAt": 1_700_000_100_000.0,
+        })
+    }
+
+    fn convex(status: Value) -> impl Fn(&Recorded) -> Reply + Send + Sync + 'static {
+        move |request| match request.function() {
+            "sessions:validateAccessToken" => Reply::value(status.clone()),
+            "sessions:getUserSessions" => Reply::value(json!([
+                document("s_other_profile", DEVICE_ID),
+                document("s_current", DEVICE_ID),
+                document("s_phone", "device-phone"),
+            ])),
+            "sessions:revokeSession" => Reply::value(json!({ "success": true })),
+            other => panic!("unexpected call to {}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn current_session_is_the_one_the_token_belongs_to() {
+        let backend = stand_in::serve(convex(json!({
+            "valid": true,
+            "userId": USER_ID,
+            "sessionId": "s_current",
+        })))
+        .await;
+
+        let sessions = list(ACCESS_TOKEN, &device_key(), &backend.client)
+            .await
+            .unwrap();
+        let current: Vec<&str> = sessions
+            .iter()
+            .filter(|session| session.is_current)
+            .map(|session| session.id.as_str())
+            .collect();
+        assert_eq!(current, vec!["s_current"]);
+
+        let requests = backend.requests();
+        assert_eq!(requests[0].body["args"], json!({ "accessToken": ACCESS_TOKEN }));
+        assert_eq!(requests[1].body["args"], json!({ "userId": USER_ID }));
+    }
+
+    #[tokio::test]
+    async fn only_other_sessions_of_the_account_are_revoked() {
+        let backend = stand_in::serve(convex(json!({
+            "valid": true,
+            "userId": USER_ID,
+            "sessionId": "s_current",
+        })))
+        .await;
+        let sessions = list(ACCESS_TOKEN, &device_key(), &backend.client)
+            .await
+            .unwrap();
+
+        assert!(matches!(revocable(&sessions, "s_current"), Err(AuthError::CurrentSession)));
+        assert!(matches!(revocable(&sessions, "s_unknown"), Err(AuthError::UnknownSession(_))));
+        // Same device id as this install, but a different session
+        let session = revocable(&sessions, "s_other_profile").unwrap();
+        revoke(&backend.client, &session.id, ACCESS_TOKEN, &device_key())
+            .await
+            .unwrap();
+
+        let requests = backend.requests();
+        let revocation = requests.last().unwrap();
+        assert_eq!(revocation.function(), "sessions:revokeSession");
+        assert_eq!(revocation.path, "/api/mutation");
+        assert_eq!(
+            revocation.body["args"],
+            json!({ "sessionId": "s_other_profile", "reason": REVOKE_REASON })
+        );
+        assert!(revocation.header("DPoP").is_some());
+    }
+
+    #[tokio::test]
+    async fn revoking_checks_the_account_list_before_the_mutation() {
+        let status = json!({ "valid": true, "userId": USER_ID, "sessionId": "s_current" });
+        let backend = stand_in::serve(convex(status.clone())).await;
+        revoke_remote("s_phone", ACCESS_TOKEN, &device_key(), &backend.client)
+            .await
+            .unwrap();
+        let calls: Vec<String> = backend
+            .requests()
+            .iter()
+            .map(|request| request.function().to_string())
+            .collect();
+        assert_eq!(
+            calls,
+            ["sessions:validateAccessToken", "sessions:getUserSessions", "sessions:revokeSession"]
+        );
+
+        let backend = stand_in::serve(convex(status)).await;
+        let result = revoke_remote("s_current", ACCESS_TOKEN, &device_key(), &backend.client).await;
+        assert!(matches!(result, Err(AuthError::CurrentSession)));
+        assert!(backend
+            .requests()
+            .iter()
+            .all(|request| request.function() != "sessions:revokeSession"));
+    }
+
+    #[tokio::test]
+    async fn listing_needs_a_token_with_an_active_session() {
+        let backend = stand_in::serve(convex(json!({ "valid": false, "reason": "token_not_found" }))).await;
+        let result = list(ACCESS_TOKEN, &device_key(), &backend.client).await;
+        assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+        assert_eq!(backend.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn only_an_inactive_session_counts_as_revoked() {
+        let cases = [
+            (json!({ "valid": false, "reason": "session_inactive" }), true),
+            (json!({ "valid": false, "reason": "token_expired" }), false),
+            (json!({ "valid": false, "reason": "token_not_found" }), false),
+            (json!({ "valid": true, "userId": USER_ID, "sessionId": "s_current" }), false),
+        ];
+        for (status, revoked) in cases {
+            let backend = stand_in::serve(convex(status.clone())).await;
+            assert_eq!(
+                is_revoked(&backend.client, ACCESS_TOKEN, &device_key()).await.unwrap(),
+                revoked,
+                "{}",
+                status
+            );
+        }
+    }
+}
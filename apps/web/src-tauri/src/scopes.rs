@@ -0,0 +1,30 @@
+use std::collections::BTreeSet;
+use crate::auth::AuthToken;
+use crate::jwt::VerifiedClaims;
+
+// Scopes the shell enforces; the backend issues them in the token's `scope` claim
+pub const LOCAL_TOOLS: &str = "local_tools";
+pub const SYNC: &str = "sync";
+
+// OAuth scope strings are space-delimited (RFC 6749 section 3.3)
+pub fn parse(scope: &str) -> BTreeSet<String> {
+    scope.split_whitespace().map(str::to_string).collect()
+}
+
+// The signed claims are authoritative. The token response's `scope` may narrow the
+// grant, but a scope missing from the claims is never granted.
+pub fn granted(token: &AuthToken, claims: &VerifiedClaims) -> Vec<String> {
+    let signed: BTreeSet<String> = claims.scopes.iter().cloned().collect();
+    match token.scope.as_deref() {
+        Some(scope) => parse(scope).intersection(&signed).cloned().collect(),
+        None => signed.into_iter().collect(),
+    }
+}
+
+pub fn missing<'a>(granted: &[String], required: &[&'a str]) -> Vec<&'a str> {
+    required
+        .iter()
+        .copied()
+        .filter(|scope| !granted.iter().any(|granted| granted == scope))
+        .collect()
+}
@@ -0,0 +1,136 @@
+use std::collections::{BTreeSet, HashMap};
+use tauri::utils::config::{CapabilityEntry, CspDirectiveSources};
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+
+// The security profile the shell is built and reviewed against. Changing tauri.conf.json
+// or the capability set without updating this profile is treated as drift.
+const EXPECTED_CSP: &[(&str, &str)] = &[
+    ("default-src", "'self'"),
+    ("script-src", "'self'"),
+    ("style-src", "'self' 'unsafe-inline'"),
+    ("img-src", "'self' data: blob: symlog-blob: symlog-asset: http://symlog-blob.localhost http://symlog-asset.localhost"),
+    ("media-src", "'self' blob: symlog-blob: symlog-asset: http://symlog-blob.localhost http://symlog-asset.localhost"),
+    ("font-src", "'self' data:"),
+    ("connect-src", "'self' ipc: http://ipc.localhost symlog-asset: http://symlog-asset.localhost https://*.convex.cloud wss://*.convex.cloud https://auth-web-two.vercel.app https://symlog-api.vercel.app https://*.crossmint.com"),
+    // Crossmint's sign-in and signer flows run in iframes
+    ("frame-src", "https://crossmint.com https://*.crossmint.com"),
+    ("object-src", "'none'"),
+    ("base-uri", "'self'"),
+    ("form-action", "'self'"),
+    ("frame-ancestors", "'none'"),
+];
+// `tauri dev` serves the frontend from the Next.js dev server, which also needs eval and
+// its websocket; everything else matches the release profile
+const EXPECTED_DEV_CSP: &[(&str, &str)] = &[
+    ("default-src", "'self'"),
+    ("script-src", "'self' 'unsafe-inline' 'unsafe-eval' http://localhost:3000"),
+    ("style-src", "'self' 'unsafe-inline' http://localhost:3000"),
+    ("img-src", "'self' data: blob: symlog-blob: symlog-asset: http://symlog-blob.localhost http://symlog-asset.localhost http://localhost:3000"),
+    ("media-src", "'self' blob: symlog-blob: symlog-asset: http://symlog-blob.localhost http://symlog-asset.localhost"),
+    ("font-src", "'self' data: http://localhost:3000"),
+    ("connect-src", "'self' ipc: http://ipc.localhost symlog-asset: http://symlog-asset.localhost http://localhost:3000 ws://localhost:3000 https://*.convex.cloud wss://*.convex.cloud https://auth-web-two.vercel.app https://symlog-api.vercel.app https://*.crossmint.com"),
+    ("frame-src", "https://crossmint.com https://*.crossmint.com"),
+    ("object-src", "'none'"),
+    ("base-uri", "'self'"),
+    ("form-action", "'self'"),
+    ("frame-ancestors", "'none'"),
+];
+const EXPECTED_CAPABILITIES: &[&str] = &["main", "auxiliary"];
+// External links go through external_links::open_external; the webview may only hand the
+// shell plugin the auth origins, matching the scoped opener permission in capabilities/main.json
+const EXPECTED_SHELL_OPEN: &str = r"^https://(auth-web-two|symlog-api)\.vercel\.app(/|$)";
+
+#[derive(Error, Debug)]
+pub enum SecurityError {
+    #[error("Runtime security config drifted from the security profile: {}", .0.join("; "))]
+    ProfileDrift(Vec<String>),
+}
+
+fn source_set(sources: &str) -> BTreeSet<String> {
+    sources.split_whitespace().map(str::to_string).collect()
+}
+
+fn check_csp(
+    label: &str,
+    csp: Option<HashMap<String, CspDirectiveSources>>,
+    expected_csp: &[(&str, &str)],
+    drift: &mut Vec<String>,
+) {
+    let Some(directives) = csp else {
+        drift.push(format!("{} is not set", label));
+        return;
+    };
+
+    for (directive, expected) in expected_csp {
+        let actual = directives
+            .get(*directive)
+            .cloned()
+            .map(|sources| Vec::<String>::from(sources).join(" "));
+        match actual {
+            Some(actual) if source_set(&actual) == source_set(expected) => {}
+            Some(actual) => drift.push(format!("{} {} is \"{}\", expected \"{}\"", label, directive, actual, expected)),
+            None => drift.push(format!("{} is missing {}", label, directive)),
+        }
+    }
+
+    for directive in directives.keys() {
+        if !expected_csp.iter().any(|(expected, _)| expected == directive) {
+            drift.push(format!("{} has unexpected directive {}", label, directive));
+        }
+    }
+}
+
+pub fn verify_security_profile(app: &AppHandle) -> Result<(), SecurityError> {
+    let config = app.config();
+    let security = &config.app.security;
+    let mut drift = Vec::new();
+
+    check_csp("csp", security.csp.clone().map(Into::into), EXPECTED_CSP, &mut drift);
+    check_csp("devCsp", security.dev_csp.clone().map(Into::into), EXPECTED_DEV_CSP, &mut drift);
+
+    // Tauri only injects script and style hashes while it is allowed to rewrite the CSP
+    for directive in ["script-src", "style-src"] {
+        if !security.dangerous_disable_asset_csp_modification.can_modify(directive) {
+            drift.push(format!("CSP modification is disabled for {}", directive));
+        }
+    }
+
+    if !security.freeze_prototype {
+        drift.push("freezePrototype is disabled".to_string());
+    }
+
+    let capabilities: BTreeSet<&str> = security
+        .capabilities
+        .iter()
+        .map(|entry| match entry {
+            CapabilityEntry::Reference(identifier) => identifier.as_str(),
+            CapabilityEntry::Inlined(capability) => capability.identifier.as_str(),
+        })
+        .collect();
+    if capabilities != EXPECTED_CAPABILITIES.iter().copied().collect() {
+        drift.push(format!(
+            "capabilities are {:?}, expected {:?}",
+            capabilities, EXPECTED_CAPABILITIES
+        ));
+    }
+
+    let shell_open = config
+        .plugins
+        .0
+        .get("shell")
+        .and_then(|shell| shell.get("open"))
+        .and_then(|open| open.as_str());
+    if shell_open != Some(EXPECTED_SHELL_OPEN) {
+        drift.push(format!("shell open is {:?}, expected {:?}", shell_open, EXPECTED_SHELL_OPEN));
+    }
+
+    if drift.is_empty() {
+        Ok(())
+    } else {
+        for problem in &drift {
+            log::error!("Security profile drift: {}", problem);
+        }
+        Err(SecurityError::ProfileDrift(drift))
+    }
+}
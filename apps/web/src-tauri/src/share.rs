@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration as StdDuration;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use chrono::{DateTime, Duration, Utc};
+use url::Url;
+use uuid::Uuid;
+use thiserror::Error;
+use crate::attachments::hex_digest;
+use crate::error::CommandError;
+use crate::files::write_atomic;
+
+// `kid=<base64url Ed25519 public key>` pairs separated by `;`. Release builds compile in the
+// project's share signing keys from this variable; at runtime it adds keys to those.
+const TRUSTED_KEYS_VAR: &str = "SYMLOG_SHARE_TRUSTED_KEYS";
+const BUILTIN_TRUSTED_KEYS: &str = match option_env!("SYMLOG_SHARE_TRUSTED_KEYS") {
+    Some(keys) => keys,
+    None => "",
+};
+const MAX_SNAPSHOT_BYTES: usize = 48 * 1024;
+const MAX_REFERENCED_BYTES: u64 = 8 * 1024 * 1024;
+// Verified links wait this long for the user to confirm
+const PENDING_TTL_MINUTES: i64 = 15;
+const MAX_PENDING_SHARES: usize = 16;
+// Imported shared conversations, also served to the webview over symlog-asset://
+pub const IMPORTED_CONVERSATIONS_DIR: &str = "conversations";
+
+#[derive(Error, Debug)]
+pub enum ShareError {
+    #[error("Malformed share link: {0}")]
+    InvalidLink(String),
+    #[error("Share link signed by untrusted key {0}")]
+    UntrustedKey(String),
+    #[error("Share link signature is invalid")]
+    BadSignature,
+    #[error("Share link expired")]
+    Expired,
+    #[error("Unknown or already handled share: {0}")]
+    UnknownShare(String),
+    #[error("Shared conversation of {0} bytes is too large")]
+    TooLarge(u64),
+    #[error("Fetching shared conversation failed: {0}")]
+    NetworkError(String),
+    #[error("Importing shared conversation failed: {0}")]
+    ImportFailed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareSender {
+    pub name: String,
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShareContent {
+    // The conversation itself, for small conversations
+    Snapshot { conversation: serde_json::Value },
+    // An HTTPS location plus the digest the fetched bytes must match
+    Reference { url: String, sha256: String, size: u64 },
+}
+
+// Signed part of `symlog://share/<payload>.<signature>`, base64url-encoded JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharePayload {
+    pub v: u32,
+    pub kid: String,
+    pub sender: ShareSender,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub content: ShareContent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharePreview {
+    pub share_id: String,
+    pub sender: ShareSender,
+    pub title: String,
+    pub kind: String,
+    pub size: u64,
+    pub message_count: Option<usize>,
+    pub key_id: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ShareOutcome {
+    Preview(SharePreview),
+    Rejected { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedConversation {
+    pub conversation_id: String,
+    pub title: String,
+    pub path: PathBuf,
+    pub imported_at: DateTime<Utc>,
+}
+
+struct PendingShare {
+    payload: SharePayload,
+    received_at: DateTime<Utc>,
+}
+
+pub struct ShareInbox {
+    trusted_keys: HashMap<String, VerifyingKey>,
+    pending: Mutex<HashMap<String, PendingShare>>,
+    client: reqwest::Client,
+}
+
+impl ShareInbox {
+    pub fn from_env() -> Self {
+        let mut trusted_keys = HashMap::new();
+        let runtime_keys = std::env::var(TRUSTED_KEYS_VAR).unwrap_or_default();
+        for entry in BUILTIN_TRUSTED_KEYS.split(';').chain(runtime_keys.split(';')) {
+            let Some((kid, key)) = entry.trim().split_once('=') else {
+                continue;
+            };
+            match decode_verifying_key(key) {
+                Ok(key) => {
+                    trusted_keys.insert(kid.to_string(), key);
+                }
+                Err(e) => log::warn!("Ignoring share key {}: {}", kid, e),
+            }
+        }
+
+        if trusted_keys.is_empty() {
+            log::warn!("No share signing keys are trusted; every share link will be refused");
+        }
+
+        Self {
+            trusted_keys,
+            pending: Mutex::new(HashMap::new()),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn verify(&self, link: &Url) -> Result<SharePayload, ShareError> {
+        let token = link
+            .path()
+            .strip_prefix('/')
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| ShareError::InvalidLink("missing payload".to_string()))?;
+        let (encoded_payload, encoded_signature) = token
+            .split_once('.')
+            .ok_or_else(|| ShareError::InvalidLink("missing signature".to_string()))?;
+
+        let payload_bytes = general_purpose::URL_SAFE_NO_PAD
+            .decode(encoded_payload)
+            .map_err(|e| ShareError::InvalidLink(e.to_string()))?;
+        let payload: SharePayload =
+            serde_json::from_slice(&payload_bytes).map_err(|e| ShareError::InvalidLink(e.to_string()))?;
+        if payload.v != 1 {
+            return Err(ShareError::InvalidLink(format!("unsupported version {}", payload.v)));
+        }
+
+        let key = self
+            .trusted_keys
+            .get(&payload.kid)
+            .ok_or_else(|| ShareError::UntrustedKey(payload.kid.clone()))?;
+        let signature_bytes = general_purpose::URL_SAFE_NO_PAD
+            .decode(encoded_signature)
+            .map_err(|_| ShareError::BadSignature)?;
+        let signature = Signature::from_slice(&signature_bytes).map_err(|_| ShareError::BadSignature)?;
+        // The signature covers the encoded segment exactly as it appears in the link
+        key.verify(encoded_payload.as_bytes(), &signature)
+            .map_err(|_| ShareError::BadSignature)?;
+
+        if payload.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            return Err(ShareError::Expired);
+        }
+        if let ShareContent::Snapshot { conversation } = &payload.content {
+            if !conversation.is_object() {
+                return Err(ShareError::InvalidLink("snapshot is not a conversation".to_string()));
+            }
+            let size = conversation.to_string().len();
+            if size > MAX_SNAPSHOT_BYTES {
+                return Err(ShareError::TooLarge(size as u64));
+            }
+        }
+        Ok(payload)
+    }
+
+    // Verifies a share link and parks it until the user confirms or it expires
+    pub fn receive(&self, link: &Url) -> ShareOutcome {
+        let payload = match self.verify(link) {
+            Ok(payload) => payload,
+            Err(e) => {
+                log::warn!("Rejected share link: {}", e);
+                return ShareOutcome::Rejected { reason: e.to_string() };
+            }
+        };
+
+        let share_id = Uuid::new_v4().to_string();
+        let preview = preview(&share_id, &payload);
+        let mut pending = self.pending.lock().unwrap();
+        let cutoff = Utc::now() - Duration::minutes(PENDING_TTL_MINUTES);
+        pending.retain(|_, share| share.received_at > cutoff);
+        if pending.len() >= MAX_PENDING_SHARES {
+            if let Some(oldest) = pending
+                .iter()
+                .min_by_key(|(_, share)| share.received_at)
+                .map(|(id, _)| id.clone())
+            {
+                pending.remove(&oldest);
+            }
+        }
+        pending.insert(
+            share_id,
+            PendingShare {
+                payload,
+                received_at: Utc::now(),
+            },
+        );
+        ShareOutcome::Preview(preview)
+    }
+
+    fn take(&self, share_id: &str) -> Result<SharePayload, ShareError> {
+        let share = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(share_id)
+            .ok_or_else(|| ShareError::UnknownShare(share_id.to_string()))?;
+        if share.received_at <= Utc::now() - Duration::minutes(PENDING_TTL_MINUTES) {
+            return Err(ShareError::UnknownShare(share_id.to_string()));
+        }
+        Ok(share.payload)
+    }
+
+    async fn fetch_reference(&self, url: &str, sha256: &str, size: u64) -> Result<serde_json::Value, ShareError> {
+        if size > MAX_REFERENCED_BYTES {
+            return Err(ShareError::TooLarge(size));
+        }
+        let parsed = Url::parse(url).map_err(|e| ShareError::InvalidLink(e.to_string()))?;
+        if parsed.scheme() != "https" {
+            return Err(ShareError::InvalidLink("referenced conversations must use HTTPS".to_string()));
+        }
+
+        let mut response = self
+            .client
+            .get(parsed)
+            .timeout(StdDuration::from_secs(30))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| ShareError::NetworkError(e.to_string()))?;
+        if let Some(length) = response.content_length().filter(|length| *length > MAX_REFERENCED_BYTES) {
+            return Err(ShareError::TooLarge(length));
+        }
+
+        // The declared length is only a hint, so the body is read in chunks and the
+        // download abandoned as soon as it passes the cap
+        let mut bytes = Vec::with_capacity(size as usize);
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| ShareError::NetworkError(e.to_string()))?
+        {
+            let received = (bytes.len() + chunk.len()) as u64;
+            if received > MAX_REFERENCED_BYTES {
+                return Err(ShareError::TooLarge(received));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        // The digest is signed, so this pins the fetched bytes to what the sender shared
+        if !hex_digest(&bytes).eq_ignore_ascii_case(sha256) {
+            return Err(ShareError::NetworkError("content does not match the signed digest".to_string()));
+        }
+
+        let conversation: serde_json::Value =
+            serde_json::from_slice(&bytes).map_err(|e| ShareError::ImportFailed(e.to_string()))?;
+        if !conversation.is_object() {
+            return Err(ShareError::ImportFailed("referenced content is not a conversation".to_string()));
+        }
+        Ok(conversation)
+    }
+}
+
+fn decode_verifying_key(encoded: &str) -> Result<VerifyingKey, String> {
+    let bytes = general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded.trim())
+        .map_err(|e| e.to_string())?;
+    let bytes: [u8; 32] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| "expected 32 bytes".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| e.to_string())
+}
+
+fn preview(share_id: &str, payload: &SharePayload) -> SharePreview {
+    let (kind, size, message_count) = match &payload.content {
+        ShareContent::Snapshot { conversation } => (
+            "snapshot",
+            conversation.to_string().len() as u64,
+            conversation
+                .get("messages")
+                .and_then(|messages| messages.as_array())
+                .map(Vec::len),
+        ),
+        ShareContent::Reference { size, .. } => ("reference", *size, None),
+    };
+    SharePreview {
+        share_id: share_id.to_string(),
+        sender: payload.sender.clone(),
+        title: payload.title.clone(),
+        kind: kind.to_string(),
+        size,
+        message_count,
+        key_id: payload.kid.clone(),
+        created_at: payload.created_at,
+        expires_at: payload.expires_at,
+    }
+}
+
+// Tauri commands
+#[command]
+pub async fn confirm_share_import(
+    share_id: String,
+    app: AppHandle,
+    inbox: State<'_, ShareInbox>,
+) -> Result<ImportedConversation, CommandError> {
+    let payload = inbox.take(&share_id)?;
+    let conversation = match &payload.content {
+        ShareContent::Snapshot { conversation } => conversation.clone(),
+        ShareContent::Reference { url, sha256, size } => inbox.fetch_reference(url, sha256, *size).await?,
+    };
+
+    let conversation_id = Uuid::new_v4().to_string();
+    let imported_at = Utc::now();
+    let path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| ShareError::ImportFailed(e.to_string()))?
+        .join(IMPORTED_CONVERSATIONS_DIR)
+        .join(format!("{}.json", conversation_id));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| ShareError::ImportFailed(e.to_string()))?;
+    }
+
+    let record = json!({
+        "id": conversation_id,
+        "title": payload.title,
+        "importedAt": imported_at,
+        "importedFrom": {
+            "sender": payload.sender,
+            "keyId": payload.kid,
+            "sharedAt": payload.created_at,
+        },
+        "conversation": conversation,
+    });
+    let bytes = serde_json::to_vec_pretty(&record).map_err(|e| ShareError::ImportFailed(e.to_string()))?;
+    write_atomic(&path, &bytes).map_err(|e| ShareError::ImportFailed(e.to_string()))?;
+
+    let imported = ImportedConversation {
+        conversation_id,
+        title: payload.title,
+        path,
+        imported_at,
+    };
+    log::info!("Imported shared conversation {}", imported.conversation_id);
+    if let Err(e) = app.emit("conversation_imported", &imported) {
+        log::error!("Failed to emit conversation import event: {}", e);
+    }
+    Ok(imported)
+}
+
+#[command]
+pub async fn reject_share_import(
+    share_id: String,
+    inbox: State<'_, ShareInbox>,
+) -> Result<(), CommandError> {
+    inbox.take(&share_id)?;
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use chrono::{DateTime, Utc};
+use zeroize::Zeroizing;
+use crate::auth::{AuthError, AuthManager, AuthToken};
+use crate::backend::BackendClient;
+use crate::dpop::DeviceKey;
+use crate::error::CommandError;
+use crate::jwt::JwtValidator;
+use crate::scopes;
+
+// A verified sign-in, held only in memory and only until the token expires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultSession {
+    pub user_id: String,
+    pub scopes: Vec<String>,
+    pub unlocked_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultStatus {
+    pub locked: bool,
+    pub session: Option<VaultSession>,
+}
+
+// Locked explicitly, by the OS locking the screen, or from the start when a session is
+// stored; otherwise it starts unlocked with no session
+#[derive(Default)]
+pub struct Vault {
+    status: RwLock<VaultStatus>,
+    // Kept for the shell's own backend calls; never serialized back to the webview
+    access_token: RwLock<Option<Zeroizing<String>>>,
+    // Locked only because the OS locked the screen or went to sleep, not by the user
+    system_locked: AtomicBool,
+}
+
+impl Vault {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A stored session stays sealed until the user unlocks or signs in again
+    pub fn locked() -> Self {
+        let vault = Self::default();
+        vault.status.write().unwrap().locked = true;
+        vault
+    }
+
+    pub fn status(&self) -> VaultStatus {
+        self.status.read().unwrap().clone()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.status.read().unwrap().locked
+    }
+
+    pub fn active_session(&self) -> Option<VaultSession> {
+        let status = self.status.read().unwrap();
+        if status.locked {
+            return None;
+        }
+        status.session.clone().filter(|session| session.expires_at > Utc::now())
+    }
+
+    // Returns whether the vault was unlocked before
+    pub fn lock(&self, auth_manager: &AuthManager) -> bool {
+        self.system_locked.store(false, Ordering::SeqCst);
+        self.seal(auth_manager)
+    }
+
+    // Same as lock, but undone by resume_after_system_lock; a lock the user asked for is kept
+    pub fn lock_for_system(&self, auth_manager: &AuthManager) -> bool {
+        let was_unlocked = self.seal(auth_manager);
+        if was_unlocked {
+            self.system_locked.store(true, Ordering::SeqCst);
+        }
+        was_unlocked
+    }
+
+    fn seal(&self, auth_manager: &AuthManager) -> bool {
+        let was_unlocked = !std::mem::replace(&mut self.status.write().unwrap().locked, true);
+        auth_manager.forget_keys();
+        was_unlocked
+    }
+
+    // Called once the OS lets the user back in. The session and token were kept, so the
+    // vault returns to where it was; keys are derived again on first use.
+    pub fn resume_after_system_lock(&self) -> bool {
+        if !self.system_locked.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        self.status.write().unwrap().locked = false;
+        true
+    }
+
+    pub fn unlock(&self, session: VaultSession, access_token: &str) {
+        self.system_locked.store(false, Ordering::SeqCst);
+        *self.access_token.write().unwrap() = Some(Zeroizing::new(access_token.to_string()));
+        *self.status.write().unwrap() = VaultStatus {
+            locked: false,
+            session: Some(session),
+        };
+    }
+
+    // Only a token the backend or issuer vouches for opens the vault
+    pub async fn unlock_with(
+        &self,
+        token: &AuthToken,
+        validator: &JwtValidator,
+        device_key: &DeviceKey,
+        backend: &BackendClient,
+    ) -> Result<(), AuthError> {
+        let claims = validator.verify(&token.access_token, device_key, backend).await?;
+        let session = VaultSession {
+            scopes: scopes::granted(token, &claims),
+            user_id: claims.user_id,
+            unlocked_at: Utc::now(),
+            expires_at: claims.expires_at,
+        };
+        self.unlock(session, &token.access_token);
+        Ok(())
+    }
+
+    // The token of the active session, if any
+    pub fn access_token(&self) -> Option<Zeroizing<String>> {
+        self.active_session()?;
+        self.access_token.read().unwrap().clone()
+    }
+
+    // Forgets the session but leaves the lock state alone
+    pub fn end_session(&self) {
+        self.access_token.write().unwrap().take();
+        self.status.write().unwrap().session = None;
+    }
+}
+
+// Tauri commands
+#[command]
+pub async fn get_vault_status(vault: State<'_, Vault>) -> Result<VaultStatus, CommandError> {
+    Ok(vault.status())
+}
+
+#[command]
+pub async fn lock_vault(vault: State<'_, Vault>, auth_manager: State<'_, AuthManager>) -> Result<(), CommandError> {
+    if vault.lock(&auth_manager) {
+        log::info!("Vault locked");
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn unlock_vault(
+    token: AuthToken,
+    vault: State<'_, Vault>,
+    validator: State<'_, JwtValidator>,
+    device_key: State<'_, DeviceKey>,
+    backend: State<'_, BackendClient>,
+) -> Result<VaultStatus, CommandError> {
+    vault.unlock_with(&token, &validator, &device_key, &backend).await?;
+    log::info!("Vault unlocked");
+    Ok(vault.status())
+}
@@ -0,0 +1,223 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+use crate::auth::AuthError;
+use crate::deep_link::{ProtocolRegistration, SchemeHandlerState, SchemeHandlerStatus};
+
+// Same name the deep-link plugin uses, so both paths converge on one entry
+fn desktop_file_name() -> Result<String, AuthError> {
+    let exe = tauri::utils::platform::current_exe().map_err(|e| AuthError::DeepLinkError(e.to_string()))?;
+    let name = exe
+        .file_name()
+        .ok_or_else(|| AuthError::DeepLinkError("executable has no file name".to_string()))?;
+    Ok(format!("{}-handler.desktop", name.to_string_lossy()))
+}
+
+fn applications_dir(app: &AppHandle) -> Result<PathBuf, AuthError> {
+    let data_home = match std::env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => app
+            .path()
+            .data_dir()
+            .map_err(|e| AuthError::DeepLinkError(e.to_string()))?,
+    };
+    Ok(data_home.join("applications"))
+}
+
+// AppImages must be launched through the AppImage, not the extracted binary
+fn qualified_exec(app: &AppHandle) -> Result<String, AuthError> {
+    let exec = match app.env().appimage {
+        Some(appimage) => PathBuf::from(appimage),
+        None => tauri::utils::platform::current_exe().map_err(|e| AuthError::DeepLinkError(e.to_string()))?,
+    };
+    Ok(exec_line(&exec))
+}
+
+// Desktop Entry quoting: `"`, `` ` ``, `$` and `\` are backslash-escaped inside the quotes and
+// `%` is doubled so it is not read as a field code. The value is also a string, whose own
+// escaping doubles every backslash, so `"` is written `\\"` and a literal backslash as four.
+fn exec_line(exec: &Path) -> String {
+    let mut quoted = String::new();
+    for c in exec.to_string_lossy().chars() {
+        match c {
+            '"' | '`' | '$' => {
+                quoted.push_str("\\\\");
+                quoted.push(c);
+            }
+            '\\' => quoted.push_str("\\\\\\\\"),
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    format!("\"{}\" %u", quoted)
+}
+
+fn product_name(app: &AppHandle) -> String {
+    app.config()
+        .product_name
+        .clone()
+        .unwrap_or_else(|| "SYMLog".to_string())
+}
+
+fn desktop_entry(name: &str, exec: &str, schemes: &[&str]) -> String {
+    let mime_types: String = schemes
+        .iter()
+        .map(|scheme| format!("x-scheme-handler/{};", scheme))
+        .collect();
+    format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec={}\nTerminal=false\nMimeType={}\nNoDisplay=true\n",
+        name, exec, mime_types
+    )
+}
+
+// An entry needs repair when it launches something else or misses one of our schemes
+fn entry_is_current(path: &Path, exec: &str, schemes: &[&str]) -> bool {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return false;
+    };
+    let field = |name: &str| {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .map(str::trim)
+    };
+    let mime_types: Vec<&str> = field("MimeType").unwrap_or_default().split(';').collect();
+    field("Exec") == Some(exec)
+        && schemes
+            .iter()
+            .all(|scheme| mime_types.contains(&format!("x-scheme-handler/{}", scheme).as_str()))
+}
+
+fn query_default(mime_type: &str) -> Result<Option<String>, String> {
+    let output = Command::new("xdg-mime")
+        .args(["query", "default", mime_type])
+        .output()
+        .map_err(|e| format!("xdg-mime is unavailable: {}", e))?;
+    let handler = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(Some(handler).filter(|h| !h.is_empty()))
+}
+
+fn set_default(file_name: &str, mime_type: &str) -> Result<(), String> {
+    let status = Command::new("xdg-mime")
+        .args(["default", file_name, mime_type])
+        .status()
+        .map_err(|e| format!("xdg-mime is unavailable: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("xdg-mime default exited with {}", status))
+    }
+}
+
+fn register_scheme(file_name: &str, scheme: &str, entry_repaired: bool, force: bool) -> SchemeHandlerState {
+    let mime_type = format!("x-scheme-handler/{}", scheme);
+    let current = match query_default(&mime_type) {
+        Ok(current) => current,
+        Err(reason) => return SchemeHandlerState::Failed { reason },
+    };
+
+    match current.as_deref() {
+        Some(handler) if handler == file_name && !entry_repaired => return SchemeHandlerState::AlreadyRegistered,
+        Some(handler) if handler != file_name && !force => {
+            return SchemeHandlerState::RegisteredToOther {
+                handler: handler.to_string(),
+            }
+        }
+        _ => {}
+    }
+
+    if let Err(reason) = set_default(file_name, &mime_type) {
+        return SchemeHandlerState::Failed { reason };
+    }
+    // Trust the association only once xdg-mime reports it back
+    match query_default(&mime_type) {
+        Ok(Some(handler)) if handler == file_name => SchemeHandlerState::Fixed,
+        Ok(handler) => SchemeHandlerState::Failed {
+            reason: format!("association did not take effect, handler is {:?}", handler),
+        },
+        Err(reason) => SchemeHandlerState::Failed { reason },
+    }
+}
+
+pub fn register_schemes(app: &AppHandle, schemes: &[&str], force: bool) -> Result<ProtocolRegistration, AuthError> {
+    let file_name = desktop_file_name()?;
+    let exec = qualified_exec(app)?;
+    let dir = applications_dir(app)?;
+    let path = dir.join(&file_name);
+
+    let entry_repaired = !entry_is_current(&path, &exec, schemes);
+    if entry_repaired {
+        fs::create_dir_all(&dir).map_err(|e| AuthError::DeepLinkError(e.to_string()))?;
+        fs::write(&path, desktop_entry(&product_name(app), &exec, schemes)).map_err(|e| AuthError::DeepLinkError(e.to_string()))?;
+        log::info!("Wrote URL scheme handler entry {}", path.display());
+
+        // Missing on minimal systems; xdg-mime still works from mimeapps.list without it
+        if let Err(e) = Command::new("update-desktop-database").arg(&dir).status() {
+            log::warn!("update-desktop-database is unavailable: {}", e);
+        }
+    }
+
+    let schemes = schemes
+        .iter()
+        .map(|scheme| {
+            let state = register_scheme(&file_name, scheme, entry_repaired, force);
+            if let SchemeHandlerState::Failed { reason } = &state {
+                log::error!("Failed to register {}:// handler: {}", scheme, reason);
+            }
+            SchemeHandlerStatus {
+                scheme: scheme.to_string(),
+                state,
+            }
+        })
+        .collect();
+
+    Ok(ProtocolRegistration {
+        platform: std::env::consts::OS.to_string(),
+        desktop_entry: Some(path),
+        desktop_entry_repaired: entry_repaired,
+        schemes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_paths_are_escaped_for_desktop_entries() {
+        assert_eq!(exec_line(Path::new("/opt/SYMLog/symlog")), "\"/opt/SYMLog/symlog\" %u");
+        assert_eq!(
+            exec_line(Path::new("/home/a b/100%/$HOME/`x`/\"q\"/symlog")),
+            r#""/home/a b/100%%/\\$HOME/\\`x\\`/\\"q\\"/symlog" %u"#
+        );
+        assert_eq!(exec_line(Path::new("/tmp/back\\slash")), r#""/tmp/back\\\\slash" %u"#);
+    }
+
+    #[test]
+    fn entries_are_current_only_with_our_exec_and_every_scheme() {
+        let dir = std::env::temp_dir().join(format!("symlog-xdg-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("symlog-handler.desktop");
+        let exec = exec_line(Path::new("/opt/SYMLog/symlog"));
+
+        assert!(!entry_is_current(&path, &exec, &["symlog"]));
+
+        fs::write(&path, desktop_entry("SYMLog", &exec, &["symlog", "symlog-dev"])).unwrap();
+        assert!(entry_is_current(&path, &exec, &["symlog", "symlog-dev"]));
+        assert!(entry_is_current(&path, &exec, &["symlog"]));
+        assert!(!entry_is_current(&path, &exec, &["symlog", "other"]));
+        assert!(!entry_is_current(&path, &exec_line(Path::new("/usr/bin/symlog")), &["symlog"]));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn desktop_entries_list_every_scheme() {
+        let entry = desktop_entry("SYMLog", "\"/opt/symlog\" %u", &["symlog", "symlog-dev"]);
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains("\nName=SYMLog\n"));
+        assert!(entry.contains("\nExec=\"/opt/symlog\" %u\n"));
+        assert!(entry.contains("\nMimeType=x-scheme-handler/symlog;x-scheme-handler/symlog-dev;\n"));
+    }
+}
@@ -8,6 +8,7 @@ import { AnimatedBackground } from "@/components/animated-background";
 import { AppMenuBar } from "@/components/app-menu-bar";
 import { TauriMenuHandler } from "@/components/tauri-menu-handler";
 import { TauriDetector } from "@/components/tauri-detector";
+import { TauriSessionNotices } from "@/components/tauri-session-notices";
 import { TauriKeyboardShortcuts } from "@/components/tauri-keyboard-shortcuts";
 import { TauriWindowControls } from "@/components/tauri-window-controls";
 
@@ -40,6 +41,7 @@ export default function RootLayout({
           <AnimatedBackground />
           <TauriMenuHandler />
           <TauriDetector />
+          <TauriSessionNotices />
           <TauriWindowControls />
           <TauriKeyboardShortcuts />
           <div className="relative flex min-h-screen flex-col tauri-app:pt-10">
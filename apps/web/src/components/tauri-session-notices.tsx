@@ -0,0 +1,80 @@
+"use client"
+
+import { useEffect } from 'react'
+import { toast } from 'sonner'
+
+// Mirrors RecoveryReport in src-tauri/src/auth_recovery.rs
+interface RecoveryReport {
+  reason: string
+  backup_path: string
+  quarantined_at: string
+}
+
+// Mirrors the payload of SESSION_REVOKED_EVENT in src-tauri/src/remote_sessions.rs
+interface SessionRevoked {
+  reason: string
+}
+
+// The shell may report the same recovery both as an event and from the late-listener query
+const SHOWN_KEY = 'symlog_auth_recovery_shown'
+
+function showRecovery(report: RecoveryReport) {
+  if (sessionStorage.getItem(SHOWN_KEY) === report.quarantined_at) return
+  sessionStorage.setItem(SHOWN_KEY, report.quarantined_at)
+  toast.warning('Your saved sign-in data could not be read and was reset', {
+    description: `Please sign in again. The damaged file was kept at ${report.backup_path}.`,
+    duration: Infinity,
+  })
+}
+
+// The shell has already cleared its sessions and locked the vault; drop the cached user
+// so the next load shows the signed-out state
+function showRevoked() {
+  localStorage.removeItem('symlog_auth_user')
+  toast.error('You were signed out on this device', {
+    description: 'This session was revoked from another device.',
+    duration: Infinity,
+    action: { label: 'Sign in again', onClick: () => window.location.reload() },
+  })
+}
+
+// Explains things the desktop shell did to the session on its own
+export function TauriSessionNotices() {
+  useEffect(() => {
+    if (typeof window === 'undefined' || !window.__TAURI__) return
+    const invoke = window.__TAURI__.invoke
+    const unlisteners: (() => void)[] = []
+    let cancelled = false
+
+    const subscribe = async () => {
+      const { listen } = await import('@tauri-apps/api/event')
+      const stops = await Promise.all([
+        listen<RecoveryReport>('auth_store_recovered', (event) => {
+          showRecovery(event.payload)
+        }),
+        listen<SessionRevoked>('session_revoked', () => {
+          showRevoked()
+        }),
+      ])
+      if (cancelled) {
+        stops.forEach((stop) => stop())
+        return
+      }
+      unlisteners.push(...stops)
+
+      // The event is emitted during startup, usually before this page has loaded
+      const report: RecoveryReport | null = await invoke('get_auth_store_recovery')
+      if (report) showRecovery(report)
+    }
+    subscribe().catch((error) => {
+      console.error('Failed to subscribe to session notices:', error)
+    })
+
+    return () => {
+      cancelled = true
+      unlisteners.forEach((stop) => stop())
+    }
+  }, [])
+
+  return null
+}
@@ -32,6 +32,8 @@ import {
   Monitor
 } from "lucide-react"
 import { getPKCEVerifier, clearPKCEVerifier } from "@/lib/auth/pkce"
+import { subscribeDeepLinks } from "@/lib/deep-links"
+import { isCommandError } from "@/types/command-errors"
 
 
 
@@ -71,9 +73,17 @@ export function WebAuthFlow() {
 
     setIsValidatingCode(true)
     try {
-      const result = await validateAuthCode({ 
-        authCode: code.trim()
-      })
+      // In the desktop app the shell makes the exchange with a DPoP proof of the device key
+      const result = typeof window !== 'undefined' && window.__TAURI__
+        ? await window.__TAURI__.invoke('exchange_auth_code', { authCode: code.trim(), codeVerifier })
+            .then((grant: any) => ({
+              userId: grant.user_id,
+              userEmail: grant.email,
+              walletAddress: grant.wallet_address,
+            }))
+        : await validateAuthCode({ 
+            authCode: code.trim()
+          })
       
       const newUser: AuthUser = {
         id: result.userId,
@@ -93,17 +103,27 @@ export function WebAuthFlow() {
       toast.success("Authentication successful!", {
         description: `Welcome back, ${newUser.email}`
       })
-    } catch (error: any) {
-      // Auth code validation failed
-      
-      // Handle Convex connection errors gracefully
-      if (error?.message?.includes('ConvexError') || error?.message?.includes('Connection failed')) {
-        toast.error("Connection Error", {
-          description: "Unable to validate code. Please check your connection and try again."
-        })
+    } catch (error: unknown) {
+      if (isCommandError(error)) {
+        switch (error.code) {
+          case 'auth.network':
+            toast.error("Connection Error", {
+              description: "Unable to validate code. Please check your connection and try again."
+            })
+            break
+          case 'auth.invalid_code':
+          case 'auth.expired_code':
+          case 'auth.pkce_failed':
+            toast.error("Authentication failed", {
+              description: "Invalid or expired code. Please sign in again."
+            })
+            break
+          default:
+            toast.error("Authentication failed", { description: error.message })
+        }
       } else {
         toast.error("Authentication failed", {
-          description: error?.message || "Invalid or expired code"
+          description: error instanceof Error ? error.message : "Invalid or expired code"
         })
       }
     } finally {
@@ -114,49 +134,64 @@ export function WebAuthFlow() {
   // Check for existing session on mount
   useEffect(() => {
     const savedUser = localStorage.getItem('symlog_auth_user')
-    if (savedUser) {
-      try {
-        setUser(JSON.parse(savedUser))
-      } catch (error) {
-        console.error('Failed to parse saved user:', error)
-        localStorage.removeItem('symlog_auth_user')
-      }
+    if (!savedUser) return
+    let saved: AuthUser
+    try {
+      saved = JSON.parse(savedUser)
+    } catch (error) {
+      console.error('Failed to parse saved user:', error)
+      localStorage.removeItem('symlog_auth_user')
+      return
+    }
+    if (typeof window === 'undefined' || !window.__TAURI__) {
+      setUser(saved)
+      return
     }
-  }, [])
-
-  // Listen for deep link auth codes from Tauri
-  useEffect(() => {
-    let unlisten: (() => void) | undefined
 
-    const setupDeepLinkListener = async () => {
-      try {
-        // Check if we're in Tauri environment
-        if (typeof window !== 'undefined' && window.__TAURI__) {
-          const { listen } = await import('@tauri-apps/api/event')
-          
-          unlisten = await listen<{ authCode: string; codeVerifier?: string }>('auth-code-received', (event) => {
-            // Auth code received from deep link
-            const { authCode, codeVerifier } = event.payload
-            
-            // Store PKCE verifier if provided
-            if (codeVerifier) {
-              sessionStorage.setItem(`pkce_verifier_${authCode}`, codeVerifier)
-            }
-            
-            handleAuthCode(authCode)
-          })
+    // The desktop shell starts locked while a session is stored; signing in again opens it
+    window.__TAURI__.invoke('get_vault_status')
+      .then((status: { locked: boolean }) => {
+        if (status.locked) {
+          toast.info("Sign in again to unlock SYMLog")
+          setShowAuthDialog(true)
+        } else {
+          setUser(saved)
         }
-      } catch (error) {
-        console.error('Failed to setup deep link listener:', error)
-      }
-    }
+      })
+      .catch((error: unknown) => {
+        console.error('Failed to read vault status:', error)
+      })
+  }, [setUser, setShowAuthDialog])
 
-    setupDeepLinkListener()
+  // Sign-in callbacks arriving as symlog:// deep links in the desktop app
+  useEffect(() => {
+    let unsubscribe: (() => void) | undefined
+    let cancelled = false
+
+    subscribeDeepLinks((link) => {
+      const authCode = link.parsed_params.code
+      if (link.route !== 'auth_callback' || !authCode) return false
+      // Callbacks that do not answer a sign-in started here are only used once the
+      // user confirms; any local app can open a symlog:// link
+      if (link.requires_confirmation &&
+          !window.confirm('A sign-in link was opened. Sign in to SYMLog with that account?')) {
+        return true
+      }
+      if (link.parsed_params.verifier) {
+        sessionStorage.setItem(`pkce_verifier_${authCode}`, link.parsed_params.verifier)
+      }
+      handleAuthCode(authCode)
+      return true
+    })
+      .then((stop) => {
+        if (cancelled) stop()
+        else unsubscribe = stop
+      })
+      .catch((error) => console.error('Failed to setup deep link listener:', error))
 
     return () => {
-      if (unlisten) {
-        unlisten()
-      }
+      cancelled = true
+      unsubscribe?.()
     }
   }, [handleAuthCode])
 
@@ -248,14 +283,19 @@ export function WebAuthFlow() {
     // Open the auth website in external browser
     try {
       if (typeof window !== 'undefined' && window.__TAURI__) {
-        // In Tauri, use shell plugin through IPC
-        window.__TAURI__.invoke('plugin:shell|open', { path: authUrl }).then(() => {
+        // In Tauri, start a sign-in the shell expects a callback for; the auth portal hands
+        // the state back on the deep link. The shell checks the URL against its origin allowlist
+        const invoke = window.__TAURI__.invoke
+        invoke('generate_auth_session').then((session: { state: string }) => {
+          const url = new URL(authUrl)
+          url.searchParams.set('state', session.state)
+          return invoke('open_auth_url', { url: url.toString() })
+        }).then(() => {
           setShowAuthDialog(true)
           setIsLoading(false)
         }).catch(() => {
-          // Fallback to window.open if Tauri API is not available
-          window.open(authUrl, '_blank')
-          setShowAuthDialog(true)
+          // No window.open fallback: a rejected URL must not reach the browser another way
+          toast.error("Failed to open authentication page")
           setIsLoading(false)
         })
       } else {
@@ -0,0 +1,64 @@
+/**
+ * Deep links received by the desktop shell (see src-tauri/src/deep_link.rs)
+ *
+ * The shell buffers every link until the page calls `deep_link_ready`, then emits later
+ * links as `deep_link` events. Each link is acknowledged once handled so it is never
+ * handed out twice. A reloaded page collects the links its predecessor never
+ * acknowledged with `drain_deep_links`.
+ */
+
+export type DeepLinkRoute = 'auth_callback' | 'share'
+
+export interface DeepLinkEvent {
+  id: string
+  url: string
+  parsed_params: Record<string, string>
+  timestamp: string
+  route: DeepLinkRoute
+  // The route has a side effect that must wait for the user
+  requires_confirmation: boolean
+  share?: unknown
+}
+
+// Returns true once the link is dealt with; unhandled links stay in the shell's inbox
+export type DeepLinkHandler = (event: DeepLinkEvent) => boolean | Promise<boolean>
+
+// Survives reloads of the webview, so a second page load knows the shell is already ready
+const READY_KEY = 'symlog_deep_links_ready'
+
+// Links can arrive both as an event and from the ready/drain calls
+const dispatched = new Set<string>()
+
+export async function subscribeDeepLinks(handler: DeepLinkHandler): Promise<() => void> {
+  if (typeof window === 'undefined' || !window.__TAURI__) {
+    return () => {}
+  }
+  const invoke = window.__TAURI__.invoke
+  const { listen } = await import('@tauri-apps/api/event')
+
+  const dispatch = async (event: DeepLinkEvent) => {
+    if (dispatched.has(event.id)) return
+    dispatched.add(event.id)
+    try {
+      if (await handler(event)) {
+        await invoke('ack_deep_link', { id: event.id })
+      }
+    } catch (error) {
+      console.error('Failed to handle deep link:', error)
+    }
+  }
+
+  // Listen first so nothing emitted between the two calls is missed
+  const unlisten = await listen<DeepLinkEvent>('deep_link', (event) => {
+    void dispatch(event.payload)
+  })
+
+  const reloaded = sessionStorage.getItem(READY_KEY) === '1'
+  const pending: DeepLinkEvent[] = await invoke(reloaded ? 'drain_deep_links' : 'deep_link_ready')
+  sessionStorage.setItem(READY_KEY, '1')
+  for (const event of pending) {
+    await dispatch(event)
+  }
+
+  return unlisten
+}
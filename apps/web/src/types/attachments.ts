@@ -46,6 +46,7 @@ export const SUPPORTED_IMAGE_TYPES = [
 
 export const SUPPORTED_DOCUMENT_TYPES = [
   'application/pdf',
+  'application/vnd.openxmlformats-officedocument.wordprocessingml.document',
   'text/plain',
   'text/markdown',
   'text/csv',
@@ -0,0 +1,61 @@
+// Mirrors ErrorCode in src-tauri/src/error.rs. Codes are stable and never reused.
+export type CommandErrorCode =
+  | 'auth.invalid_code'
+  | 'auth.expired_code'
+  | 'auth.pkce_failed'
+  | 'auth.storage'
+  | 'auth.crypto'
+  | 'auth.invalid_url'
+  | 'auth.deep_link'
+  | 'auth.invalid_token'
+  | 'auth.network'
+  | 'auth.confirmation_required'
+  | 'auth.unknown_session'
+  | 'auth.current_session'
+  | 'file.unknown_handle'
+  | 'file.access_denied'
+  | 'file.chunk_too_large'
+  | 'file.out_of_order_write'
+  | 'file.invalid_path'
+  | 'file.policy_violation'
+  | 'file.invalid_artifact'
+  | 'file.encoding'
+  | 'file.io'
+  | 'blob.not_found'
+  | 'blob.invalid_hash'
+  | 'blob.quota_exceeded'
+  | 'blob.storage'
+  | 'share.invalid_link'
+  | 'share.untrusted_key'
+  | 'share.bad_signature'
+  | 'share.expired'
+  | 'share.unknown'
+  | 'share.too_large'
+  | 'share.network'
+  | 'share.import_failed'
+  | 'link.invalid_url'
+  | 'link.blocked'
+  | 'link.declined'
+  | 'link.open_failed'
+  | 'ipc.denied'
+  | 'ipc.vault_locked'
+  | 'ipc.session_required'
+  | 'ipc.missing_scope'
+  | 'internal';
+
+export interface CommandError {
+  code: CommandErrorCode;
+  message: string;
+  retryable: boolean;
+  details?: Record<string, unknown>;
+}
+
+export function isCommandError(error: unknown): error is CommandError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as CommandError).code === 'string' &&
+    typeof (error as CommandError).message === 'string' &&
+    typeof (error as CommandError).retryable === 'boolean'
+  );
+}
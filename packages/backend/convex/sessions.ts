@@ -14,6 +14,12 @@ function generateSecureToken(length: number = 32): string {
   return token
 }
 
+/**
+ * Scopes granted to every session; there are no per-user grants yet.
+ * Space-delimited, as in an OAuth token response.
+ */
+const SESSION_SCOPE = "local_tools sync"
+
 /**
  * Generate JWT access token (simplified - in production use proper JWT library)
  */
@@ -24,7 +30,8 @@ function generateAccessToken(userId: string): string {
     sub: userId,
     iat: Math.floor(Date.now() / 1000),
     exp: Math.floor(Date.now() / 1000) + (15 * 60), // 15 minutes
-    type: "access"
+    type: "access",
+    scope: SESSION_SCOPE
   }
   
   // Simplified encoding - use proper JWT library in production
@@ -98,6 +105,7 @@ export const createSession = async (
       refreshToken,
       accessTokenExpiresAt,
       refreshTokenExpiresAt,
+      scope: SESSION_SCOPE,
     }
 }
 
@@ -167,6 +175,7 @@ export const refreshAccessToken = mutation({
       refreshToken: newRefreshToken,
       accessTokenExpiresAt,
       refreshTokenExpiresAt,
+      scope: SESSION_SCOPE,
     }
   },
 })
@@ -203,6 +212,8 @@ export const validateAccessToken = query({
       valid: true,
       userId: session.userId,
       sessionId: session._id,
+      accessTokenExpiresAt: session.accessTokenExpiresAt,
+      scope: SESSION_SCOPE,
       user,
     }
   },